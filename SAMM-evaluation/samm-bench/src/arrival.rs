@@ -0,0 +1,207 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rand_distr::Exp;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+// When the transactions of a client are sent. The expected TPS of a run is the base rate;
+// the time-varying processes multiply it by a factor that depends on the time since the start
+// of the run, the same for every client so that bursts hit the AMM at once.
+// Declared in the experiment file as the [arrival] table, e.g.
+//   [arrival]
+//   process = "on_off"
+//   on_factor = 3.0
+//   off_factor = 0.2
+//   mean_on = 10.0
+//   mean_off = 20.0
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "process", rename_all = "snake_case")]
+pub enum ArrivalProcess
+{
+    // one transaction every 1 / rate seconds, from a random phase
+    Constant,
+    // exponential inter-arrival times (the original behaviour)
+    #[default]
+    Poisson,
+    // Markov-modulated Poisson process with two states: the rate is multiplied by on_factor
+    // during "on" periods and by off_factor during "off" periods, whose lengths are exponential
+    // with means mean_on and mean_off seconds
+    OnOff { on_factor: f64, off_factor: f64, mean_on: f64, mean_off: f64 },
+    // Poisson with the rate multiplied by 1 + amplitude * sin(2 pi t / period)
+    Diurnal { period: f64, amplitude: f64 },
+    // Poisson with the rate multiplied by factor from `at` seconds on
+    Step { at: f64, factor: f64 },
+    // Poisson with the rate multiplied by factor during [at, at + duration)
+    Spike { at: f64, duration: f64, factor: f64 },
+}
+
+impl ArrivalProcess
+{
+    pub fn validate(&self) -> Result<(), anyhow::Error>
+    {
+        let valid = match self {
+            ArrivalProcess::Constant | ArrivalProcess::Poisson => true,
+            ArrivalProcess::OnOff { on_factor, off_factor, mean_on, mean_off } =>
+                *on_factor >= 0.0 && *off_factor >= 0.0 && *mean_on > 0.0 && *mean_off > 0.0,
+            ArrivalProcess::Diurnal { period, amplitude } => *period > 0.0 && (0.0..=1.0).contains(amplitude),
+            ArrivalProcess::Step { at, factor } => *at >= 0.0 && *factor >= 0.0,
+            ArrivalProcess::Spike { at, duration, factor } => *at >= 0.0 && *duration >= 0.0 && *factor >= 0.0,
+        };
+        if !valid
+        {
+            return Err(anyhow::anyhow!("Invalid arrival process {:?}", self));
+        }
+        Ok(())
+    }
+
+    // Average of the rate factor over [0, horizon], used to size the transaction queues
+    pub fn mean_factor(&self, horizon: f64) -> f64
+    {
+        if horizon <= 0.0
+        {
+            return 1.0;
+        }
+        match self {
+            ArrivalProcess::Constant | ArrivalProcess::Poisson | ArrivalProcess::Diurnal { .. } => 1.0,
+            ArrivalProcess::OnOff { on_factor, off_factor, mean_on, mean_off } =>
+                (on_factor * mean_on + off_factor * mean_off) / (mean_on + mean_off),
+            ArrivalProcess::Step { at, factor } => {
+                let before = at.min(horizon);
+                (before + (horizon - before) * factor) / horizon
+            }
+            ArrivalProcess::Spike { at, duration, factor } => {
+                let spike = ((at + duration).min(horizon) - at.min(horizon)).max(0.0);
+                (horizon - spike + spike * factor) / horizon
+            }
+        }
+    }
+
+    // The rate factors of one run. The on/off periods are drawn once from the seed of the run.
+    pub fn profile(&self, seed: u64, horizon: f64) -> RateProfile
+    {
+        let mut switches = vec![];
+        if let ArrivalProcess::OnOff { mean_on, mean_off, .. } = self
+        {
+            let mut rng = StdRng::seed_from_u64(seed ^ 0x6172_7269_7661_6c73);
+            let on = Exp::new(1.0 / mean_on).unwrap();
+            let off = Exp::new(1.0 / mean_off).unwrap();
+            let mut time = 0.0;
+            while time < horizon
+            {
+                let length = if switches.len() % 2 == 0 { on.sample(&mut rng) } else { off.sample(&mut rng) };
+                time += length;
+                switches.push(time);
+            }
+        }
+        RateProfile {
+            process: self.clone(),
+            horizon,
+            switches,
+        }
+    }
+}
+
+// The rate factor of a run over time
+#[derive(Clone, Debug)]
+pub struct RateProfile
+{
+    pub process: ArrivalProcess,
+    // end of the run; no arrival is drawn beyond it
+    pub horizon: f64,
+    // end times of the alternating on/off periods, starting with "on"
+    pub switches: Vec<f64>,
+}
+
+impl RateProfile
+{
+    // Factor of the base rate at `time` seconds since the start of the run
+    pub fn factor(&self, time: f64) -> f64
+    {
+        match &self.process {
+            ArrivalProcess::Constant | ArrivalProcess::Poisson => 1.0,
+            ArrivalProcess::OnOff { on_factor, off_factor, .. } => {
+                let period = self.switches.partition_point(|end| *end <= time);
+                if period % 2 == 0 { *on_factor } else { *off_factor }
+            }
+            ArrivalProcess::Diurnal { period, amplitude } => 1.0 + amplitude * (2.0 * PI * time / period).sin(),
+            ArrivalProcess::Step { at, factor } => if time >= *at { *factor } else { 1.0 },
+            ArrivalProcess::Spike { at, duration, factor } => if time >= *at && time < at + duration { *factor } else { 1.0 },
+        }
+    }
+
+    pub fn max_factor(&self) -> f64
+    {
+        match &self.process {
+            ArrivalProcess::Constant | ArrivalProcess::Poisson => 1.0,
+            ArrivalProcess::OnOff { on_factor, off_factor, .. } => on_factor.max(*off_factor),
+            ArrivalProcess::Diurnal { amplitude, .. } => 1.0 + amplitude,
+            ArrivalProcess::Step { factor, .. } | ArrivalProcess::Spike { factor, .. } => factor.max(1.0),
+        }
+    }
+}
+
+// The arrival times of one client
+pub struct Arrivals
+{
+    // base rate of the client (transactions per second)
+    rate: f64,
+    profile: Arc<RateProfile>,
+    rng: StdRng,
+    // the next arrival of a constant-rate client
+    next_constant: Option<f64>,
+}
+
+impl Arrivals
+{
+    pub fn new(rate: f64, profile: Arc<RateProfile>, rng: StdRng) -> Self
+    {
+        Self {
+            rate,
+            profile,
+            rng,
+            next_constant: None,
+        }
+    }
+
+    // The time of the next arrival after `now`. Time-varying rates are drawn by thinning:
+    // candidates come at the peak rate and are kept with probability rate(t) / peak rate.
+    pub fn next_arrival(&mut self, now: f64) -> f64
+    {
+        if self.rate <= 0.0
+        {
+            return f64::INFINITY;
+        }
+        if self.profile.process == ArrivalProcess::Constant
+        {
+            let interval = 1.0 / self.rate;
+            // a random phase so that the clients do not send in lockstep
+            let next = match self.next_constant {
+                Some(next) => next + interval,
+                None => now + self.rng.gen::<f64>() * interval,
+            };
+            self.next_constant = Some(next);
+            return next;
+        }
+        let max_factor = self.profile.max_factor();
+        if max_factor <= 0.0
+        {
+            return f64::INFINITY;
+        }
+        let exp = Exp::new(self.rate * max_factor).unwrap();
+        let mut time = now;
+        loop
+        {
+            time += exp.sample(&mut self.rng);
+            if self.rng.gen::<f64>() * max_factor < self.profile.factor(time)
+            {
+                return time;
+            }
+            // e.g. an "off" factor of 0 until the end of the run
+            if time > self.profile.horizon
+            {
+                return time;
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::build_contract::COIN_EACH_OBJ;
+use crate::execution::ExecutionReturn;
+use crate::model::{get_fee_to_fundation, PoolModel, Pricing};
+use crate::router::read_pool;
+use crate::workload::SwapDirection;
+use serde::{Deserialize, Serialize};
+use sui_sdk::types::base_types::ObjectID;
+use sui_sdk::SuiClient;
+
+// A reserve may differ from the replay of the model by this share of the volume traded in its token
+// (plus one unit per swap): the swaps are replayed in the order they completed, which is close to
+// but not exactly the order of the chain
+pub const RESERVE_TOLERANCE: f64 = 0.001;
+
+// The pool of one shard before and after a run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ShardAudit
+{
+    pub shard: usize,
+    pub before: Option<PoolModel>,
+    pub after: Option<PoolModel>,
+    // `before` after the successful swaps of the run, replayed with the model in the order they completed
+    pub expected: Option<PoolModel>,
+    // the successful swaps recorded on the shard
+    pub swaps: usize,
+    // the successful liquidity operations recorded on the shard, which are not replayed
+    pub other_operations: usize,
+    // why the pool was not reconciled, None if it was
+    pub skipped: Option<String>,
+    // where the pool read after the run disagrees with the successful swaps
+    pub discrepancies: Vec<String>,
+}
+
+// The audit of every pool of a run
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AuditReport
+{
+    pub shards: Vec<ShardAudit>,
+}
+
+impl AuditReport
+{
+    pub fn discrepancies(&self) -> usize
+    {
+        self.shards.iter().map(|shard| shard.discrepancies.len()).sum()
+    }
+}
+
+// The pools of a run, read before it starts
+pub struct PoolAudit
+{
+    pub pricing: Pricing,
+    // the Global of each shard
+    pub globals: Vec<ObjectID>,
+    before: Vec<Option<PoolModel>>,
+}
+
+async fn read_pool_or_warn(client: &SuiClient, shard: usize, global: ObjectID, when: &str) -> Option<PoolModel>
+{
+    match read_pool(client, global).await {
+        Ok(pool) => Some(pool),
+        Err(e) => {
+            eprintln!("Failed to read the pool of shard {} {} the run: {}", shard, when, e);
+            None
+        }
+    }
+}
+
+impl PoolAudit
+{
+    pub async fn snapshot(client: &SuiClient, pricing: Pricing, globals: Vec<ObjectID>) -> Self
+    {
+        let mut before = vec![];
+        for (shard, global) in globals.iter().enumerate()
+        {
+            before.push(read_pool_or_warn(client, shard, *global, "before").await);
+        }
+        Self { pricing, globals, before }
+    }
+
+    // Read every pool again and reconcile it with the successful transactions of the run
+    pub async fn reconcile(&self, client: &SuiClient, results: &[ExecutionReturn]) -> AuditReport
+    {
+        let mut report = AuditReport::default();
+        for (shard, global) in self.globals.iter().enumerate()
+        {
+            let after = read_pool_or_warn(client, shard, *global, "after").await;
+            let mut successes: Vec<&ExecutionReturn> = results.iter()
+                .filter(|res| res.shard == shard && res.if_sucess == 1)
+                .collect();
+            successes.sort_by(|a, b| a.end_time.total_cmp(&b.end_time));
+            report.shards.push(reconcile_shard(shard, self.pricing, self.before[shard], after, &successes));
+        }
+        report
+    }
+}
+
+// Check a balance of the pool that the order of the swaps does not change
+fn check_exact(discrepancies: &mut Vec<String>, name: &str, actual: u64, expected: u64)
+{
+    if actual != expected
+    {
+        discrepancies.push(format!("{} is {} on chain, {} expected", name, actual, expected));
+    }
+}
+
+// Check a reserve of the pool, which depends on the order of the swaps
+fn check_reserve(discrepancies: &mut Vec<String>, name: &str, actual: u64, expected: u64, volume: u128, swaps: usize)
+{
+    let tolerance = RESERVE_TOLERANCE * volume as f64 + swaps as f64;
+    if (actual as f64 - expected as f64).abs() > tolerance
+    {
+        discrepancies.push(format!("{} is {} on chain, {} expected (tolerance {:.0})", name, actual, expected, tolerance));
+    }
+}
+
+// Replay the successful transactions of a shard, in the order they completed, on the pool read before
+// the run and compare with the pool read after it
+pub fn reconcile_shard(shard: usize, pricing: Pricing, before: Option<PoolModel>, after: Option<PoolModel>,
+    successes: &[&ExecutionReturn]) -> ShardAudit
+{
+    let swaps = successes.iter().filter(|res| res.swap.is_some()).count();
+    let mut audit = ShardAudit {
+        shard,
+        before,
+        after,
+        expected: None,
+        swaps,
+        other_operations: successes.len() - swaps,
+        skipped: None,
+        discrepancies: vec![],
+    };
+    let (before, after) = match (before, after) {
+        (Some(before), Some(after)) => (before, after),
+        _ => {
+            audit.skipped = Some("the pool could not be read before and after the run".to_string());
+            return audit;
+        }
+    };
+    if audit.other_operations > 0
+    {
+        audit.skipped = Some(format!("{} liquidity operations are not replayed", audit.other_operations));
+        return audit;
+    }
+    let mut expected = before;
+    let (mut volume_x, mut volume_y) = (0u128, 0u128);
+    for (res, swap) in successes.iter().filter_map(|res| res.swap.as_ref().map(|swap| (res, swap)))
+    {
+        match expected.swap(pricing, swap, COIN_EACH_OBJ as u64) {
+            Ok(outcome) => {
+                // USDT is the X of the pool
+                let (volume_in, volume_out) = match swap.direction {
+                    SwapDirection::UsdtToXbtc => (&mut volume_x, &mut volume_y),
+                    SwapDirection::XbtcToUsdt => (&mut volume_y, &mut volume_x),
+                };
+                *volume_in += outcome.amount_in as u128;
+                *volume_out += outcome.amount_out as u128;
+            }
+            Err(e) => audit.discrepancies.push(format!("swap {} succeeded on chain but aborts in the model: {}", res.digest, e)),
+        }
+    }
+    let discrepancies = &mut audit.discrepancies;
+    if swaps == 0 && after != before
+    {
+        discrepancies.push("the pool changed without any successful transaction".to_string());
+    }
+    if swaps > 0 && after.k() <= before.k()
+    {
+        discrepancies.push(format!("k did not increase: {} before, {} after", before.k(), after.k()));
+    }
+    check_exact(discrepancies, "lp_supply", after.lp_supply, expected.lp_supply);
+    // every swap pays with a coin of COIN_EACH_OBJ, so the fee set aside is the same in any order
+    check_exact(discrepancies, "fee_coin_x", after.fee_coin_x, expected.fee_coin_x);
+    check_exact(discrepancies, "fee_coin_y", after.fee_coin_y, expected.fee_coin_y);
+    if pricing == Pricing::Cpmm
+    {
+        // the fee balances count the swaps that really executed, e.g. after a timeout recorded as a failure
+        if let Ok(fee) = get_fee_to_fundation(COIN_EACH_OBJ as u64)
+        {
+            if fee > 0 && (after.fee_coin_x != expected.fee_coin_x || after.fee_coin_y != expected.fee_coin_y)
+            {
+                let executed = |after: u64, before: u64| after.saturating_sub(before) / fee;
+                discrepancies.push(format!("the fee balances count {} swaps selling USDT and {} selling XBTC",
+                    executed(after.fee_coin_x, before.fee_coin_x), executed(after.fee_coin_y, before.fee_coin_y)));
+            }
+        }
+    }
+    check_reserve(discrepancies, "coin_x", after.coin_x, expected.coin_x, volume_x, swaps);
+    check_reserve(discrepancies, "coin_y", after.coin_y, expected.coin_y, volume_y, swaps);
+    audit.expected = Some(expected);
+    audit
+}
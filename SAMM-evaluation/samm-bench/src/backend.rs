@@ -0,0 +1,203 @@
+use crate::build_contract::{ContractInfo, publish_amm_package, add_initial_liquidity, COIN_EACH_OBJ};
+use crate::workload::{Operation, SwapDirection};
+use async_trait::async_trait;
+use sui_json::SuiJsonValue;
+use sui_json_rpc_types::SuiTypeTag;
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+use sui_sdk::SuiClient;
+
+// The default output amount of each swap (SAMM) or its minimal output (OmniSwap)
+pub const SWAP_AMOUNT: u64 = 100000;
+
+// A Move call that a trader client signs before the test starts
+#[derive(Clone)]
+pub struct MoveCallSpec
+{
+    pub package: ObjectID,
+    pub module: &'static str,
+    pub function: &'static str,
+    pub type_args: Vec<SuiTypeTag>,
+    pub call_args: Vec<SuiJsonValue>,
+}
+impl MoveCallSpec
+{
+    pub fn new(package: ObjectID, module: &'static str, function: &'static str,
+        type_args: Vec<SuiTypeTag>, call_args: Vec<SuiJsonValue>) -> Self
+    {
+        Self {
+            package,
+            module,
+            function,
+            type_args,
+            call_args,
+        }
+    }
+}
+
+// An AMM under test. The harness only talks to the Move packages through this trait,
+// so adding a new AMM variant means writing one more implementation.
+#[async_trait]
+pub trait AmmBackend: Send + Sync
+{
+    // Name of the AMM, used in logs
+    fn name(&self) -> &'static str;
+
+    // Publish the AMM package (and its test coins) on the fresh network
+    async fn publish(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID)
+        -> Result<ContractInfo, anyhow::Error>;
+
+    // Create the traded pool of a published contract and provide its initial liquidity
+    async fn create_pool(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo)
+        -> Result<(), anyhow::Error>;
+
+    // The call of the operation paying with `coins` (one coin per name of op.coin_names(), in that order),
+    // signed later by a trader client
+    fn build_call(&self, contract_info: &ContractInfo, op: &Operation, coins: &[ObjectID])
+        -> Result<MoveCallSpec, anyhow::Error>;
+}
+
+// The call of an operation to the interface module, shared by all OmniBTC-style packages:
+// swap<XBTC, USDT>(global, coin_in, amount) or swap<USDT, XBTC>, the multi_ variants with vectors of one coin,
+// and the liquidity operations on the pool as created, <USDT, XBTC>
+fn interface_call(contract_info: &ContractInfo, op: &Operation, coins: &[ObjectID])
+    -> Result<MoveCallSpec, anyhow::Error>
+{
+    let USDT_id = format!("{}::coins::USDT",contract_info.coin_package);
+    let XBTC_id = format!("{}::coins::XBTC",contract_info.coin_package);
+    let names = op.coin_names();
+    if coins.len() != names.len()
+    {
+        return Err(anyhow::anyhow!("{} pays with {} coins, got {}", op.label(), names.len(), coins.len()));
+    }
+    let coin = |index: usize| coins[index].to_string().parse::<SuiJsonValue>();
+    let coin_vector = |index: usize| SuiJsonValue::new(serde_json::json!([coins[index].to_string()]));
+    let global = contract_info.global.to_string().parse::<SuiJsonValue>()?;
+    // the input token comes first in the type arguments of a swap
+    let swap_type_args = |direction: SwapDirection| match direction {
+        SwapDirection::XbtcToUsdt => vec![SuiTypeTag::new(XBTC_id.clone()), SuiTypeTag::new(USDT_id.clone())],
+        SwapDirection::UsdtToXbtc => vec![SuiTypeTag::new(USDT_id.clone()), SuiTypeTag::new(XBTC_id.clone())],
+    };
+    let pool_type_args = vec![SuiTypeTag::new(USDT_id.clone()), SuiTypeTag::new(XBTC_id.clone())];
+    let (type_args, call_args) = match op {
+        Operation::Swap(swap) => (swap_type_args(swap.direction), vec![
+            global,
+            coin(0)?,
+            swap.amount.to_string().parse::<SuiJsonValue>()?,
+        ]),
+        Operation::MultiSwap(swap) => (swap_type_args(swap.direction), vec![
+            global,
+            coin_vector(0)?,
+            COIN_EACH_OBJ.to_string().parse::<SuiJsonValue>()?,
+            swap.amount.to_string().parse::<SuiJsonValue>()?,
+        ]),
+        Operation::AddLiquidity => (pool_type_args, vec![
+            global,
+            coin(0)?,
+            "1".parse::<SuiJsonValue>()?,
+            coin(1)?,
+            "1".parse::<SuiJsonValue>()?,
+        ]),
+        Operation::MultiAddLiquidity => (pool_type_args, vec![
+            global,
+            coin_vector(0)?,
+            COIN_EACH_OBJ.to_string().parse::<SuiJsonValue>()?,
+            "1".parse::<SuiJsonValue>()?,
+            coin_vector(1)?,
+            COIN_EACH_OBJ.to_string().parse::<SuiJsonValue>()?,
+            "1".parse::<SuiJsonValue>()?,
+        ]),
+        Operation::RemoveLiquidity => (pool_type_args, vec![global, coin(0)?]),
+        Operation::MultiRemoveLiquidity => (pool_type_args, vec![global, coin_vector(0)?]),
+    };
+    Ok(MoveCallSpec::new(contract_info.packageid, "interface", op.label(), type_args, call_args))
+}
+
+// SAMM (../samm): the swap argument is the exact output amount, the input is charged by the SAMM fee
+pub struct SammBackend;
+
+#[async_trait]
+impl AmmBackend for SammBackend
+{
+    fn name(&self) -> &'static str
+    {
+        "samm"
+    }
+
+    async fn publish(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID)
+        -> Result<ContractInfo, anyhow::Error>
+    {
+        publish_amm_package(client, sender, gas_object, "samm").await
+    }
+
+    async fn create_pool(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo)
+        -> Result<(), anyhow::Error>
+    {
+        add_initial_liquidity(client, sender, gas_object, contract_info).await
+    }
+
+    fn build_call(&self, contract_info: &ContractInfo, op: &Operation, coins: &[ObjectID])
+        -> Result<MoveCallSpec, anyhow::Error>
+    {
+        interface_call(contract_info, op, coins)
+    }
+}
+
+// Heavier SAMM (../samm-boost): SAMM with extra computation in every swap
+pub struct SammBoostBackend;
+
+#[async_trait]
+impl AmmBackend for SammBoostBackend
+{
+    fn name(&self) -> &'static str
+    {
+        "samm-boost"
+    }
+
+    async fn publish(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID)
+        -> Result<ContractInfo, anyhow::Error>
+    {
+        publish_amm_package(client, sender, gas_object, "samm-boost").await
+    }
+
+    async fn create_pool(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo)
+        -> Result<(), anyhow::Error>
+    {
+        add_initial_liquidity(client, sender, gas_object, contract_info).await
+    }
+
+    fn build_call(&self, contract_info: &ContractInfo, op: &Operation, coins: &[ObjectID])
+        -> Result<MoveCallSpec, anyhow::Error>
+    {
+        interface_call(contract_info, op, coins)
+    }
+}
+
+// OmniSwap (../omniswap): the swap argument is the minimal output, the whole input coin is spent
+pub struct OmniSwapBackend;
+
+#[async_trait]
+impl AmmBackend for OmniSwapBackend
+{
+    fn name(&self) -> &'static str
+    {
+        "omniswap"
+    }
+
+    async fn publish(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID)
+        -> Result<ContractInfo, anyhow::Error>
+    {
+        publish_amm_package(client, sender, gas_object, "omniswap").await
+    }
+
+    async fn create_pool(&self, client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo)
+        -> Result<(), anyhow::Error>
+    {
+        add_initial_liquidity(client, sender, gas_object, contract_info).await
+    }
+
+    fn build_call(&self, contract_info: &ContractInfo, op: &Operation, coins: &[ObjectID])
+        -> Result<MoveCallSpec, anyhow::Error>
+    {
+        interface_call(contract_info, op, coins)
+    }
+}
@@ -0,0 +1,181 @@
+// Recompute the metrics of a finished experiment from its raw client files,
+// optionally with another measurement window, without running anything again.
+use clap::Parser;
+use samm_bench::checkpoint::Checkpoint;
+use samm_bench::config::{DEFAULT_TIME_BUCKET, DEFAULT_TIME_TEST, DEFAULT_TIME_WARM_UP};
+use samm_bench::execution::{summarize_results, ExecutionReturn, ExperimentReturn};
+use samm_bench::metrics::{fmt_opt, steady_state, time_series, SteadyState};
+use samm_bench::results::{read_client_file, read_run_records, write_histogram, write_shard_results, write_time_series, CLIENT_FILE_PREFIX, RUNS_FILE, TIME_SERIES_FILE};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(about = "Recompute throughput, failures and latencies of a result folder from its raw client files")]
+struct Args
+{
+    /// Result folder of an experiment (the one holding info.txt)
+    folder: PathBuf,
+    /// Start of the measurement window in seconds (default: the warm-up time of the experiment)
+    #[arg(long)]
+    time_warm_up: Option<f64>,
+    /// Length of the measurement window in seconds (default: the test time of the experiment)
+    #[arg(long)]
+    time_test: Option<f64>,
+    /// Width of the time-series buckets in seconds (default: the time_bucket of the experiment)
+    #[arg(long)]
+    bucket: Option<f64>,
+    /// Where to write the summaries (default: <folder>/analysis-<start>-<length>)
+    #[arg(long)]
+    out: Option<PathBuf>,
+}
+
+// "raw4" -> 4
+fn parse_raw_folder(name: &str) -> Option<usize>
+{
+    name.strip_prefix("raw")?.parse().ok()
+}
+
+// "300-test2" -> (300, 2)
+fn parse_run_folder(name: &str) -> Option<(usize, usize)>
+{
+    let (tps, repetition) = name.split_once("-test")?;
+    Some((tps.parse().ok()?, repetition.parse().ok()?))
+}
+
+fn sorted_subfolders(folder: &Path) -> Result<Vec<(String, PathBuf)>, anyhow::Error>
+{
+    let mut entries = vec![];
+    for entry in std::fs::read_dir(folder)?
+    {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+        {
+            entries.push((entry.file_name().to_string_lossy().to_string(), entry.path()));
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+fn read_run(folder: &Path) -> Result<Vec<ExecutionReturn>, anyhow::Error>
+{
+    let mut results = vec![];
+    for entry in std::fs::read_dir(folder)?
+    {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(CLIENT_FILE_PREFIX)
+        {
+            results.extend(read_client_file(&entry.path())?);
+        }
+    }
+    Ok(results)
+}
+
+fn print_shards(result: &ExperimentReturn)
+{
+    for shard in &result.shards
+    {
+        println!("    shard {}: {} sent, {} successful, {} failed, TPS {}, average latency {}, p99 latency {}",
+            shard.shard, shard.submitted, shard.success, shard.fail, shard.throughput,
+            fmt_opt(shard.latency.mean), fmt_opt(shard.latency.p99));
+    }
+    println!("    load imbalance (max/mean): {}", fmt_opt(result.load_imbalance));
+}
+
+fn print_steady_state(result: &ExperimentReturn)
+{
+    if let Some(SteadyState { reason: Some(reason), .. }) = &result.steady_state
+    {
+        println!("    not in steady state: {}", reason);
+    }
+}
+
+fn main() -> Result<(), anyhow::Error>
+{
+    let args = Args::parse();
+    // The window of the live run, if the folder has a checkpoint
+    let config = Checkpoint::load(&args.folder).ok().map(|checkpoint| checkpoint.config);
+    let time_warm_up = args.time_warm_up
+        .or(config.as_ref().map(|config| config.time_warm_up))
+        .unwrap_or(DEFAULT_TIME_WARM_UP);
+    let time_test = args.time_test
+        .or(config.as_ref().map(|config| config.time_test))
+        .unwrap_or(DEFAULT_TIME_TEST);
+    let bucket = args.bucket
+        .or(config.as_ref().map(|config| config.time_bucket))
+        .unwrap_or(DEFAULT_TIME_BUCKET);
+    let time1 = time_warm_up;
+    let time2 = time1 + time_test;
+    let out = args.out.clone()
+        .unwrap_or_else(|| args.folder.join(format!("analysis-{}-{}", time_warm_up, time_test)));
+    std::fs::create_dir_all(&out)?;
+    println!("Measurement window: [{}, {}] s, results in {}", time1, time2, out.display());
+
+    // (shards, tps, repetition) -> recomputed metrics
+    let mut recomputed = BTreeMap::new();
+    for (raw_name, raw_path) in sorted_subfolders(&args.folder)?
+    {
+        let Some(num_shards) = parse_raw_folder(&raw_name) else { continue };
+        let mut runs = vec![];
+        for (run_name, run_path) in sorted_subfolders(&raw_path)?
+        {
+            if let Some((tps, repetition)) = parse_run_folder(&run_name)
+            {
+                runs.push((tps, repetition, run_path));
+            }
+        }
+        runs.sort_by_key(|(tps, repetition, _)| (*tps, *repetition));
+
+        let mut result_file = File::create(out.join(format!("output{}.txt", num_shards)))?;
+        for (tps, repetition, run_path) in runs
+        {
+            let results = read_run(&run_path)?;
+            let mut result = summarize_results(&results, time1, time2, num_shards);
+            let series = time_series(&results, bucket);
+            result.steady_state = Some(steady_state(&series, bucket, time1, time2));
+            // Same line format as the live run
+            writeln!(
+                &mut result_file,
+                "{}, {}, {}, {}, 0, {}, {}, {}, {}, {}",
+                tps, result.success, fmt_opt(result.average_latency()), result.fail,
+                fmt_opt(result.latency.p50), fmt_opt(result.latency.p90), fmt_opt(result.latency.p99),
+                fmt_opt(result.latency.p999), fmt_opt(result.latency.max),
+            )?;
+            let run_out = out.join(&raw_name).join(format!("{}-test{}", tps, repetition));
+            std::fs::create_dir_all(&run_out)?;
+            write_histogram(&run_out, &result.histogram)?;
+            write_shard_results(&run_out, &result.shards)?;
+            write_time_series(&run_out.join(TIME_SERIES_FILE), &series)?;
+            println!("{} shards, expected TPS {}, test {}: {} successful, {} failed, TPS {}, average latency {}",
+                num_shards, tps, repetition, result.success, result.fail,
+                result.success as f64 / time_test, fmt_opt(result.average_latency()));
+            for (class, count) in &result.failures
+            {
+                println!("    {}: {}", class, count);
+            }
+            print_shards(&result);
+            print_steady_state(&result);
+            recomputed.insert((num_shards, tps, repetition), result);
+        }
+    }
+
+    // Rewrite the run records with the new window, when the live run wrote them
+    if let Ok(records) = read_run_records(&args.folder)
+    {
+        let mut runs_file = File::create(out.join(RUNS_FILE))?;
+        for mut record in records
+        {
+            if let Some(result) = recomputed.get(&(record.num_shards, record.target_tps, record.repetition))
+            {
+                record.config.time_warm_up = time_warm_up;
+                record.config.time_test = time_test;
+                record.achieved_tps = result.success as f64 / time_test;
+                record.metrics = result.clone();
+                writeln!(runs_file, "{}", serde_json::to_string(&record)?)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+// Run a long sequence of generated swaps through the off-chain model of the pools,
+// without a validator: how the SAMM fee and the shard count shape what the traders pay.
+use clap::Parser;
+use samm_bench::build_contract::{ceil_divide, COIN_EACH_OBJ, POOLCOIN};
+use samm_bench::config::ExperimentSpec;
+use samm_bench::model::{simulate, PoolModel, Pricing};
+use samm_bench::workload::{uniform_plan, OperationMix, ShardPopularity, SwapMix};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Simulate swaps on the off-chain model of SAMM or CPMM pools")]
+struct Args
+{
+    /// Experiment file whose [swaps] and [shard_popularity] tables draw the swaps
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Number of swaps
+    #[arg(long, default_value_t = 100000)]
+    swaps: usize,
+    /// Number of shards (pools) sharing the swaps
+    #[arg(long, default_value_t = 1)]
+    shards: usize,
+    /// Initial reserve of each token of each pool
+    #[arg(long, default_value_t = POOLCOIN as u64)]
+    reserve: u64,
+    /// Value of the coin paying each swap
+    #[arg(long, default_value_t = COIN_EACH_OBJ as u64)]
+    coin_value: u64,
+    /// samm (exact output, SAMM fee) or cpmm (OmniSwap)
+    #[arg(long, value_enum, default_value_t = Pricing::Samm)]
+    pricing: Pricing,
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<(), anyhow::Error>
+{
+    let args = Args::parse();
+    let spec = match &args.config {
+        Some(path) => ExperimentSpec::from_file(path)?,
+        None => ExperimentSpec::default(),
+    };
+    let mix: SwapMix = spec.swaps.unwrap_or_default();
+    mix.validate()?;
+    if args.shards == 0 || args.swaps == 0
+    {
+        return Err(anyhow::anyhow!("--shards and --swaps must be positive"));
+    }
+    let popularity: ShardPopularity = spec.shard_popularity.unwrap_or_default();
+    popularity.validate(args.shards)?;
+    let pricing = args.pricing;
+    // one client holding every swap, in the order they are drawn
+    let per_shard = ceil_divide(args.swaps as u64, args.shards as u64)? as usize;
+    let plan = uniform_plan(1, args.shards, per_shard, args.seed, &popularity, &OperationMix::default(), &mix);
+    let txs: Vec<_> = plan.into_iter().flatten().take(args.swaps).collect();
+    println!("{} swaps on {} {:?} pools of {} of each token, paid with coins of {}", txs.len(), args.shards, pricing, args.reserve, args.coin_value);
+    for shard in 0..args.shards
+    {
+        let mut pool = PoolModel::new(args.reserve, args.reserve)
+            .map_err(|e| anyhow::anyhow!("Cannot create the pool: {}", e))?;
+        let k = pool.k();
+        let swaps: Vec<_> = txs.iter().filter(|tx| tx.shard == shard).filter_map(|tx| tx.op.swap()).collect();
+        let summary = simulate(&mut pool, pricing, swaps.iter().copied(), args.coin_value);
+        println!("Shard {}: {} executed, {} aborted, input {} USDT / {} XBTC, fees {} USDT / {} XBTC",
+            shard, summary.executed, summary.aborted, summary.amount_in_x, summary.amount_in_y, summary.fee_x, summary.fee_y);
+        for (error, count) in &summary.errors
+        {
+            println!("    {}: {}", error, count);
+        }
+        println!("    reserves {} USDT / {} XBTC, k grew by {}", pool.coin_x, pool.coin_y, pool.k() - k);
+    }
+    Ok(())
+}
@@ -0,0 +1,683 @@
+use crate::build_tx::TestTransactionSender;
+use crate::faucet::{get_and_and_split_gas_obj, get_gas_obj_one_layer};
+use crate::execution::call_swap_before_sign;
+use crate::build_tx::{DataAndSender, MOVE_CALL_GAS_BUDGET};
+use crate::offline::OfflineBuilder;
+use crate::backend::{AmmBackend, MoveCallSpec};
+use crate::router::Route;
+use crate::signer::Signer;
+use crate::workload::WorkloadPlan;
+use anyhow::Ok;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time:: Duration;
+use tokio::time::sleep;
+use tokio;
+use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use sui_json::SuiJsonValue;
+use sui_json_rpc_types::{SuiExecutionStatus, SuiTransactionBlockEffectsAPI};
+use sui_json_rpc_types::{SuiTypeTag, ObjectChange};
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+use sui_sdk::SuiClient;
+
+
+pub const MAX_COIN_PER_PROCESS: usize = 1000;
+
+
+pub const ONECOIN: usize = 100000000;
+pub const POOLCOIN: usize = 1000000000000;
+pub const COIN_EACH_OBJ: usize = 10000000;
+pub const MAX_SPLIT_COUNT: usize = 1000;
+pub const GAS_SPLIT: usize = 1000;
+// A faucet coin split for gas pools gives fewer, larger coins, each paying for many transactions
+pub const POOL_GAS_SPLIT: usize = 20;
+
+
+
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ContractInfo{
+    pub packageid: ObjectID,
+    pub global: ObjectID,
+    pub coin_package: ObjectID,
+    pub faucet_id: ObjectID,
+}
+impl ContractInfo {
+    pub fn new(packageid: ObjectID, global: ObjectID, coin_package: ObjectID, faucet_id: ObjectID,) -> Self {
+        Self {
+            packageid,
+            global,
+            coin_package,
+            faucet_id,
+        }
+    }
+}
+
+
+
+pub async fn get_one_coin_obj(client: SuiClient, sender: SuiAddress, coin_package:ObjectID, 
+    faucet_id: ObjectID, gas_object: ObjectID, coin_type: String, coin_amount: String)
+    -> Result<ObjectID, anyhow::Error>
+{
+    let test_transaction_sender = TestTransactionSender::new(sender, gas_object, client.clone());
+    let mut coin_obj = "0x".parse::<ObjectID>()?;
+    let call_args = vec![
+        faucet_id.to_string().parse::<SuiJsonValue>()?,     
+        coin_amount.parse::<SuiJsonValue>()?,  
+    ];
+    let type_args = vec![
+        SuiTypeTag::new(coin_type),        
+    ];
+    let transaction_response = test_transaction_sender.move_call(coin_package, "faucet", "force_claim", type_args, call_args).await?;
+    //println!("{:?}", transaction_response.object_changes);
+    if transaction_response.clone().effects.unwrap().status().is_ok() != true
+    {
+        panic!();
+    }
+    let obj_changes = transaction_response.clone().object_changes.unwrap();
+    for item in &obj_changes {
+        match item {
+            ObjectChange::Created {
+                sender,
+                owner,
+                object_type,
+                object_id,
+                version,
+                digest,
+            } => {
+                coin_obj = *object_id;
+            }
+            _ => {
+                
+            }
+        }
+    }
+    Ok(coin_obj)
+}
+
+
+pub async fn split_coins(client: SuiClient, sender: SuiAddress, coin_id:ObjectID, gas_object: ObjectID, split_count: u64)
+    -> Result<Vec<ObjectID>, anyhow::Error>
+{
+    let mut coin_list: Vec<ObjectID> = vec![];
+    coin_list.push(coin_id);
+    if split_count > 1
+    {
+        let test_transaction_sender = TestTransactionSender::new(sender, gas_object, client.clone());
+        let transaction_response = test_transaction_sender.split_coin_equal(coin_id, split_count ).await?;
+        // println!("{:?}", transaction_response);
+        if transaction_response.clone().effects.unwrap().status().is_ok() != true
+        {
+            panic!();
+        }
+        let obj_changes = transaction_response.clone().object_changes.unwrap();
+        for item in &obj_changes {
+            match item {
+                ObjectChange::Created {
+                    sender,
+                    owner,
+                    object_type,
+                    object_id,
+                    version,
+                    digest,
+                } => {
+                    coin_list.push(*object_id);
+                }
+                _ => {
+                    
+                }
+            }
+        }
+    }
+    
+    // println!("{:?}", coin_list);
+    Ok(coin_list)
+}
+
+pub fn ceil_divide(num1: u64, num2: u64)->Result<u64, anyhow::Error>
+{
+    let result = if num1 % (num2 as u64) == 0 {
+        num1 /  (num2 as u64)
+    } else {
+        num1 / num2 as u64 + 1
+    };
+    Ok(result)
+}
+
+
+pub async fn split_coins_paralell(client: SuiClient, sender: SuiAddress, coin_id:ObjectID, gas_object: ObjectID, split_count: u64)
+    -> Result<Vec<ObjectID>, anyhow::Error>
+{
+
+    let total_process = ceil_divide(split_count, MAX_COIN_PER_PROCESS as u64)?;
+    let mut large_coin_list = vec![];
+    let mut medium_coin_count:u64= 0;
+    let mut result: Vec<ObjectID> = vec![];
+    if total_process <= MAX_COIN_PER_PROCESS as u64
+    {
+        large_coin_list.push(coin_id);
+        medium_coin_count = total_process;
+    }
+    else 
+    {
+        let large_coin_num = ceil_divide(total_process, MAX_COIN_PER_PROCESS as u64)?;
+        medium_coin_count = ceil_divide(total_process, large_coin_num)?;
+        large_coin_list = split_coins(client.clone(), sender, coin_id, gas_object, total_process).await?;
+    }
+    let gas_obj_list = get_gas_obj_one_layer(medium_coin_count as usize, sender).await?;
+    for large_coin in large_coin_list
+    {
+        let mut tasks = Vec::new();   
+        let Task_queue = split_coins(client.clone(), sender, large_coin, gas_object, medium_coin_count).await?;
+        for i in 0..total_process
+        {
+            let client_clone = client.clone();
+            let this_gas_obj = gas_obj_list.clone()[i as usize].parse::<ObjectID>()?.clone();
+            let this_coin_id = Task_queue[i as usize];
+            // println!("{:?}", this_coin_id);
+            let task = tokio::spawn(async move {
+            split_coins(client_clone, sender, this_coin_id, this_gas_obj, MAX_COIN_PER_PROCESS as u64).await
+            });
+            tasks.push(task);
+        }
+        for task in tasks 
+        {
+            let result_part = task.await??;
+            result.extend(result_part);
+        }
+    }
+    
+    Ok(result)
+}
+
+
+fn update_toml(package_dir: &str, package_id: ObjectID, sender: SuiAddress)->Result<(), anyhow::Error>
+{
+    // We need to update the Move.toml file with the package_id and the sender address
+    let destination_file = format!("../{}/Move.toml", package_dir);
+    let package_amm = package_id.to_string(); 
+    let address = sender.to_string();
+    let file = File::open(&destination_file)?;
+    let reader = BufReader::new(file);
+    let mut new_lines = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        new_lines.push(line.clone());
+
+        if line.contains(r#"version = "1.0.0""#) {
+            new_lines.push(format!("published-at=\"{}\"", package_amm));
+        }
+
+        if line.contains("swap = ") {
+            if let Some(last_line) = new_lines.last_mut() {
+                *last_line = format!(r#"swap = "{}""#, package_amm);
+            }
+        }
+
+        if line.contains("controller = ") {
+            if let Some(last_line) = new_lines.last_mut() {
+                *last_line = format!(r#"controller = "{}""#, address);
+            }
+        }
+
+        if line.contains("beneficiary = ") {
+            if let Some(last_line) = new_lines.last_mut() {
+                *last_line = format!(r#"beneficiary = "{}""#, address);
+            }
+        }
+    }
+
+    let mut file = File::create(&destination_file)?;
+    for line in new_lines {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+
+// Publish an OmniBTC-style AMM package in `package_dir` and its test coins, and register the sender as faucet admin
+pub async fn publish_amm_package(client: SuiClient, sender: SuiAddress, gas_object: ObjectID, package_dir: &str)-> Result<ContractInfo, anyhow::Error>
+{
+    // Initiate Move.toml file (will change due to test coins after each experiement)
+    let source_file_path = format!("../{}/Move_backup.toml", package_dir);
+    let target_file_path = format!("../{}/Move.toml", package_dir);
+    fs::copy(source_file_path, target_file_path)?;
+    // Results needed
+    let mut packageid = "0x1".parse::<ObjectID>()?;
+    let mut global = "0x1".parse::<ObjectID>()?;
+    let mut coin_package = "0x1".parse::<ObjectID>()?;
+    let mut faucet_id = "0x1".parse::<ObjectID>()?;
+
+    let test_transaction_sender = TestTransactionSender::new(sender, gas_object, client.clone());
+    let transaction_response = test_transaction_sender.publish_package(package_dir).await?;
+    let obj_changes = transaction_response.clone().object_changes.unwrap();
+
+    for item in &obj_changes {
+        match item {
+            ObjectChange::Published {
+                package_id,
+                version,
+                digest,
+                modules,
+            } => {
+                packageid = *package_id;
+            }
+            ObjectChange::Created {
+                sender,
+                owner,
+                object_type,
+                object_id,
+                version,
+                digest,
+            } => {
+                if object_type.name.to_string() == "Global"
+                {
+                    global = *object_id;
+                }
+            }
+            _ => {
+                
+            }
+        }
+    }
+    // Publish test coins
+    update_toml(package_dir, packageid, sender)?;
+    let test_transaction_sender = TestTransactionSender::new(sender, gas_object, client.clone());
+    let transaction_response = test_transaction_sender.publish_package(&format!("{}/test_coins", package_dir)).await?;
+    if transaction_response.clone().effects.unwrap().status().is_ok() != true
+    {
+        panic!();
+    }
+    let obj_changes = transaction_response.clone().object_changes.unwrap();
+    for item in &obj_changes {
+        match item {
+            ObjectChange::Published {
+                package_id,
+                version,
+                digest,
+                modules,
+            } => {
+                coin_package = *package_id;
+            }
+            ObjectChange::Created {
+                sender,
+                owner,
+                object_type,
+                object_id,
+                version,
+                digest,
+            } => {
+                if object_type.name.to_string() == "Faucet"
+                {
+                    faucet_id = *object_id;
+                }
+            }
+            _ => {
+                
+            }
+        }
+    }
+
+    // add admin
+    let contract_info = ContractInfo::new(packageid, global, coin_package, faucet_id);
+    add_faucet_admins(client, sender, gas_object, &contract_info, &[sender]).await?;
+
+    Ok(contract_info)
+}
+
+// Let admins claim test coins and add liquidity from the faucet of the contract, in one programmable
+// transaction. Only the sender that published the test coins may add an admin, and each admin only once
+pub async fn add_faucet_admins(client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo, admins: &[SuiAddress])
+    -> Result<(), anyhow::Error>
+{
+    if admins.is_empty()
+    {
+        return Ok(());
+    }
+    let mut calls = vec![];
+    for admin in admins
+    {
+        let call_args = vec![
+            contract_info.faucet_id.to_string().parse::<SuiJsonValue>()?,
+            admin.to_string().parse::<SuiJsonValue>()?,
+        ];
+        calls.push(MoveCallSpec::new(contract_info.coin_package, "faucet", "add_admin", vec![], call_args));
+    }
+    let mut builder = OfflineBuilder::new(&client, MOVE_CALL_GAS_BUDGET).await?;
+    builder.resolve_package(&client, contract_info.coin_package).await?;
+    builder.resolve_shared(&client, contract_info.faucet_id).await?;
+    builder.resolve_objects(&client, &[gas_object]).await?;
+    let tx_data = builder.build_batch(sender, &calls, gas_object)?;
+    let transaction_response = TestTransactionSender::new(sender, gas_object, client).sign_and_send(tx_data).await?;
+    match transaction_response.effects.as_ref().map(|effects| effects.status()) {
+        Some(SuiExecutionStatus::Success) => Ok(()),
+        status => Err(anyhow::anyhow!("Adding {:?} as admins of faucet {} failed: {:?}", admins, contract_info.faucet_id, status)),
+    }
+}
+
+
+// Claim `coin_amount` (in ONECOIN) of both test coins, add them to the USDT-XBTC pool and return the LP coin
+pub async fn add_liquidity(client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo, coin_amount: String)-> Result<ObjectID, anyhow::Error>
+{
+    let USDT_id = format!("{}::coins::USDT",contract_info.coin_package).to_string();
+    let XBTC_id = format!("{}::coins::XBTC",contract_info.coin_package).to_string();
+
+    //Get coins and put it in the pool
+    // command = f"sui client call  --gas-budget 100000000 --package {package_coin} --module faucet --function force_claim --args {faucetID} {math.ceil(POOLCOIN / ONECOIN)} --type-args {USDT_id}"
+    let USDT_object = get_one_coin_obj(client.clone(), sender, contract_info.coin_package, contract_info.faucet_id, gas_object, USDT_id.clone(), coin_amount.clone()).await?;
+    
+    let XBTC_object = get_one_coin_obj(client.clone(), sender, contract_info.coin_package, contract_info.faucet_id, gas_object, XBTC_id.clone(), coin_amount).await?;
+
+    // Add liquidity to the pool
+    // command = f"sui client call --gas-budget 100000000 --package={package_AMM} --module=interface --function=add_liquidity --args {GlobalID} {USDT_obj} 1 {XBTC_obj} 1 --type-args {USDT_id} {XBTC_id}"
+    let test_transaction_sender = TestTransactionSender::new(sender, gas_object, client.clone());
+    let call_args = vec![
+        contract_info.global.to_string().parse::<SuiJsonValue>()?,     
+        USDT_object.to_string().parse::<SuiJsonValue>()?,
+        "1".parse::<SuiJsonValue>()?,
+        XBTC_object.to_string().parse::<SuiJsonValue>()?,
+        "1".parse::<SuiJsonValue>()?,
+    ];
+    let type_args = vec![
+        SuiTypeTag::new(USDT_id.clone()),
+        SuiTypeTag::new(XBTC_id.clone()),        
+    ];
+    let transaction_response = test_transaction_sender.move_call(contract_info.packageid, "interface", "add_liquidity", type_args, call_args).await?;
+    if transaction_response.clone().effects.unwrap().status().is_ok() != true
+    {
+        panic!();
+    }
+    // the LP coin is the only created object of type Coin<LP<USDT, XBTC>>
+    let obj_changes = transaction_response.clone().object_changes.unwrap();
+    for item in &obj_changes {
+        if let ObjectChange::Created { object_type, object_id, .. } = item
+        {
+            if object_type.to_string().contains("::implements::LP<")
+            {
+                return Ok(*object_id);
+            }
+        }
+    }
+    Err(anyhow::anyhow!("add_liquidity created no LP coin"))
+}
+
+
+// Claim POOLCOIN of both test coins and add them as the initial liquidity of the USDT-XBTC pool
+pub async fn add_initial_liquidity(client: SuiClient, sender: SuiAddress, gas_object: ObjectID, contract_info: &ContractInfo)-> Result<(), anyhow::Error>
+{
+    add_liquidity(client, sender, gas_object, contract_info, (POOLCOIN/ONECOIN).to_string()).await?;
+    Ok(())
+}
+
+
+// Mint `count` LP coins of the USDT-XBTC pool, each worth about COIN_EACH_OBJ of both tokens,
+// for the remove_liquidity transactions: one position is added and split in parallel.
+// The count is rounded up to a multiple of MAX_COIN_PER_PROCESS.
+pub async fn mint_lp_coins(client: SuiClient, sender: SuiAddress, contract_info: &ContractInfo, gas_object: ObjectID, count: usize)
+    -> Result<Vec<ObjectID>, anyhow::Error>
+{
+    if count == 0
+    {
+        return Ok(vec![]);
+    }
+    let total_coin = MAX_COIN_PER_PROCESS as u64 * ceil_divide(count as u64, MAX_COIN_PER_PROCESS as u64)?;
+    let lp_coin = add_liquidity(client.clone(), sender, gas_object, contract_info, ((total_coin as usize) * COIN_EACH_OBJ /ONECOIN).to_string()).await?;
+    split_coins_paralell(client, sender, lp_coin, gas_object, total_coin).await
+}
+
+
+// Claim `count` coins of COIN_EACH_OBJ of a test coin (XBTC or USDT) and split them in parallel.
+// The count is rounded up to a multiple of MAX_COIN_PER_PROCESS.
+pub async fn claim_split_coins(client: SuiClient, sender: SuiAddress, contract_info: &ContractInfo, gas_object: ObjectID, coin_name: &str, count: usize)
+    -> Result<Vec<ObjectID>, anyhow::Error>
+{
+    if count == 0
+    {
+        return Ok(vec![]);
+    }
+    let coin_type = format!("{}::coins::{}", contract_info.coin_package, coin_name);
+    let total_coin = MAX_COIN_PER_PROCESS as u64 * ceil_divide(count as u64, MAX_COIN_PER_PROCESS as u64)?;
+    // faucet from the testcoin
+    let large_coin = get_one_coin_obj(client.clone(), sender, contract_info.coin_package, contract_info.faucet_id, gas_object, coin_type, ((total_coin as usize) * COIN_EACH_OBJ /ONECOIN).to_string()).await?;
+    // split the coins into small coins
+    split_coins_paralell(client, sender, large_coin, gas_object, total_coin).await
+}
+
+
+// An account sending the transactions of trader clients, with the gas coin paying for its setup
+#[derive(Clone, Copy, Debug)]
+pub struct TraderAccount
+{
+    pub address: SuiAddress,
+    pub gas_object: ObjectID,
+}
+
+// Request gas from the faucet for each address
+pub async fn fund_trader_accounts(addresses: &[SuiAddress]) -> Result<Vec<TraderAccount>, anyhow::Error>
+{
+    let requests = addresses.iter().map(|address| get_gas_obj_one_layer(5, *address));
+    let mut accounts = vec![];
+    for (address, obj_list) in addresses.iter().zip(join_all(requests).await)
+    {
+        let gas_object = obj_list?.first().ok_or_else(|| anyhow::anyhow!("The faucet sent no gas to {}", address))?.parse::<ObjectID>()?;
+        accounts.push(TraderAccount { address: *address, gas_object });
+    }
+    Ok(accounts)
+}
+
+// gas_coins coins for each of num_clients clients, split from the gas of the account of the client
+async fn split_pool_gas(client: &SuiClient, accounts: &[TraderAccount], num_clients: usize, gas_coins: usize)
+    -> Result<Vec<Vec<ObjectID>>, anyhow::Error>
+{
+    let mut pool_gas = vec![vec![]; num_clients];
+    for (a, account) in accounts.iter().enumerate()
+    {
+        let clients: Vec<usize> = (a..num_clients).step_by(accounts.len()).collect();
+        if clients.is_empty()
+        {
+            continue;
+        }
+        let num_large_gas = ceil_divide((clients.len() * gas_coins) as u64, POOL_GAS_SPLIT as u64)?;
+        let mut gas_list = get_and_and_split_gas_obj(client.clone(), num_large_gas as usize, account.address, POOL_GAS_SPLIT).await?.into_iter();
+        for c in clients
+        {
+            pool_gas[c] = gas_list.by_ref().take(gas_coins).collect();
+            if pool_gas[c].len() < gas_coins
+            {
+                return Err(anyhow::anyhow!("Not enough gas coins for the gas pool of client {}", c));
+            }
+        }
+    }
+    Ok(pool_gas)
+}
+
+// Deploy num_contracts pools of the given AMM with sender and pre-sign the planned transactions of every client.
+// Client c sends as accounts[c % accounts.len()], which owns the coins and gas of its transactions.
+// With route_swaps, every swap is also built on every other shard, so that a router can pick its shard
+// when it is sent; the candidate it picks is signed then. With offline, the transactions are built locally instead of by the fullnode.
+// With gas_coins, the transactions of each client are left unsigned, to be signed when they are sent
+// with one of the gas_coins coins of its gas pool, instead of each pinning a gas coin of its own.
+// Returns the queue of each client and the contracts.
+pub async fn amm_data_builder<B: AmmBackend>(backend: &B, client: SuiClient, sender: SuiAddress, num_contracts: usize,
+    plan: &WorkloadPlan, gas_object: ObjectID, accounts: &[TraderAccount], gas_coins: Option<usize>, route_swaps: bool, offline: bool)
+    -> Result<(Vec<Vec<DataAndSender>>, Vec<ContractInfo>), anyhow::Error>
+{
+    // client c pays the gas of all its transactions with the coins pool_gas[c]
+    let pool_gas = match gas_coins {
+        Some(gas_coins) => Some(split_pool_gas(&client, accounts, plan.len(), gas_coins).await?),
+        None => None,
+    };
+    let mut offline_builder = if offline {
+        Some(OfflineBuilder::new(&client, MOVE_CALL_GAS_BUDGET).await?)
+    } else {
+        None
+    };
+    let mut execution_queue: Vec<Vec<Option<DataAndSender>>> = plan.iter().map(|txs| vec![None; txs.len()]).collect();
+    // the candidates of each routed swap, in shard order
+    let mut candidates: Vec<Vec<Vec<DataAndSender>>> = plan.iter().map(|txs| vec![vec![]; txs.len()]).collect();
+    let routed = |c: usize, k: usize| route_swaps && plan[c][k].op.swap().is_some();
+    let mut contracts = vec![];
+    // generate the contracts and coins in each contract
+    for i in 0..num_contracts
+    {
+        println!("Start to generate {} contract {}", backend.name(), i);
+        let contractinfo = backend.publish(client.clone(), sender, gas_object).await?;
+        backend.create_pool(client.clone(), sender, gas_object, &contractinfo).await?;
+        // the trader accounts claim their coins from the faucet themselves
+        let mut traders: Vec<SuiAddress> = accounts.iter().map(|account| account.address).filter(|address| *address != sender).collect();
+        traders.sort();
+        traders.dedup();
+        add_faucet_admins(client.clone(), sender, gas_object, &contractinfo, &traders).await?;
+        // (client, position) of the transactions of this contract, and of the routed swaps
+        let jobs: Vec<(usize, usize)> = plan.iter().enumerate()
+            .flat_map(|(c, txs)| txs.iter().enumerate().map(move |(k, tx)| (c, k, tx.shard)))
+            .filter(|(c, k, shard)| *shard == i || routed(*c, *k))
+            .map(|(c, k, _)| (c, k))
+            .collect();
+        // mint the coins every transaction pays with, owned by the account of its client:
+        // test coins from the faucet, LP coins from a position
+        let account_of = |c: usize| c % accounts.len();
+        let mut needed: BTreeMap<(usize, &str), usize> = BTreeMap::new();
+        let mut jobs_of_account = vec![0u64; accounts.len()];
+        for (c, k) in &jobs
+        {
+            for name in plan[*c][*k].op.coin_names()
+            {
+                *needed.entry((account_of(*c), name)).or_insert(0) += 1;
+            }
+            jobs_of_account[account_of(*c)] += 1;
+        }
+        let mut coins = HashMap::new();
+        for ((a, name), count) in needed
+        {
+            let account = accounts[a];
+            let minted = if name == "LP" {
+                mint_lp_coins(client.clone(), account.address, &contractinfo, account.gas_object, count).await?
+            } else {
+                claim_split_coins(client.clone(), account.address, &contractinfo, account.gas_object, name, count).await?
+            };
+            coins.insert((a, name), minted.into_iter());
+        }
+        let mut gas_lists = vec![];
+        for (account, num_jobs) in accounts.iter().zip(jobs_of_account)
+        {
+            let gas_list = if num_jobs > 0 && pool_gas.is_none() {
+                let num_large_gas = ceil_divide(num_jobs, GAS_SPLIT as u64)?;
+                get_and_and_split_gas_obj(client.clone(), num_large_gas as usize, account.address, GAS_SPLIT).await?
+            } else {
+                vec![]
+            };
+            gas_lists.push(gas_list.into_iter());
+        }
+        let mut calls = vec![];
+        let mut object_ids = vec![];
+        for (c, k) in &jobs
+        {
+            let a = account_of(*c);
+            let tx = &plan[*c][*k];
+            // the coin of a gas pool is only a placeholder, replaced when the transaction is signed
+            let gas_obj = match &pool_gas {
+                Some(pool_gas) => pool_gas[*c][*k % pool_gas[*c].len()],
+                None => gas_lists[a].next()
+                    .ok_or_else(|| anyhow::anyhow!("Not enough gas coins for account {} on contract {}", accounts[a].address, i))?,
+            };
+            let tx_coins = tx.op.coin_names().into_iter()
+                .map(|name| coins.get_mut(&(a, name)).and_then(|minted| minted.next())
+                    .ok_or_else(|| anyhow::anyhow!("Not enough {} coins for contract {}", name, i)))
+                .collect::<Result<Vec<ObjectID>, anyhow::Error>>()?;
+            let call = backend.build_call(&contractinfo, &tx.op, &tx_coins)?;
+            object_ids.extend(tx_coins);
+            object_ids.push(gas_obj);
+            calls.push((*c, *k, accounts[a].address, call, gas_obj));
+        }
+        // generate the transactions, signed below all together
+        let mut unsigned = vec![];
+        if let Some(builder) = offline_builder.as_mut()
+        {
+            builder.resolve_package(&client, contractinfo.packageid).await?;
+            builder.resolve_shared(&client, contractinfo.global).await?;
+            object_ids.sort();
+            object_ids.dedup();
+            builder.resolve_objects(&client, &object_ids).await?;
+            for (c, k, account, call, gas_obj) in calls
+            {
+                let tx_data = builder.build(account, &call, gas_obj)?;
+                unsigned.push((c, k, TestTransactionSender::new(account, gas_obj, client.clone()), tx_data));
+            }
+        }
+        else
+        {
+            let mut tasks = vec![];
+            for (c, k, account, call, gas_obj) in calls
+            {
+                let client_clone = client.clone();
+                let task= tokio::spawn(async move {
+                    call_swap_before_sign(client_clone, account, gas_obj, call).await
+                    });
+                tasks.push((c, k, task));
+                let duration = Duration::from_secs_f64(0.0001);
+                sleep(duration).await;
+            }
+            for (c, k, task) in tasks 
+            {
+                let (test_sender, tx_data) = task.await??;
+                unsigned.push((c, k, test_sender, tx_data));
+            }
+        }
+        // sign the planned transactions of the contract on the rayon pool, unless they are signed when sent;
+        // the candidates of routed swaps planned on another shard are only signed if the router picks them
+        let (to_sign, candidates_only): (Vec<_>, Vec<_>) = unsigned.into_iter()
+            .partition(|(c, k, _, _)| pool_gas.is_none() && plan[*c][*k].shard == i);
+        let mut transactions: Vec<(usize, usize, DataAndSender)> = candidates_only.into_iter()
+            .map(|(c, k, test_sender, tx_data)| (c, k, DataAndSender::unsigned(test_sender, tx_data)))
+            .collect();
+        if !to_sign.is_empty()
+        {
+            let signer = Signer::shared();
+            let (to_sign, signatures) = tokio::task::spawn_blocking(move || {
+                let txs: Vec<_> = to_sign.iter().map(|(_, _, test_sender, tx_data)| (test_sender.sender, tx_data)).collect();
+                let signatures = signer.sign_batch(&txs);
+                (to_sign, signatures)
+            }).await?;
+            transactions.extend(to_sign.into_iter().zip(signatures?)
+                .map(|((c, k, test_sender, tx_data), signature)| (c, k, DataAndSender::new(signature, test_sender, tx_data))));
+        }
+        for (c, k, mut result_part) in transactions
+        {
+            result_part.shard = i;
+            result_part.operation = plan[c][k].op.label();
+            result_part.send_at = plan[c][k].send_at;
+            result_part.swap = plan[c][k].op.swap().cloned();
+            if routed(c, k)
+            {
+                candidates[c][k].push(result_part.clone());
+            }
+            if plan[c][k].shard == i
+            {
+                execution_queue[c][k] = Some(result_part);
+            }
+        }
+        contracts.push(contractinfo);
+        println!("Contract {} finished.", i);
+    }
+    let mut queues = vec![];
+    for (c, txs) in execution_queue.into_iter().enumerate()
+    {
+        let mut queue = vec![];
+        for (k, tx) in txs.into_iter().enumerate()
+        {
+            let mut tx = tx.ok_or_else(|| anyhow::anyhow!("A planned transaction targets a missing contract"))?;
+            if let Some(swap) = plan[c][k].op.swap().filter(|_| routed(c, k))
+            {
+                tx.route = Some(Route { swap: swap.clone(), candidates: std::mem::take(&mut candidates[c][k]) });
+            }
+            queue.push(tx);
+        }
+        queues.push(queue);
+    }
+    Ok((queues, contracts))
+}
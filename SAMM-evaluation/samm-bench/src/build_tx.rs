@@ -1,46 +1,86 @@
 // Copyright (c) Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
-use sui_config::{
-    sui_config_dir, SUI_KEYSTORE_FILENAME,
-};
 use sui_json::SuiJsonValue;
 use sui_json_rpc_types::{SuiTransactionBlockResponse, SuiTypeTag};
-use sui_keys::keystore::{AccountKeystore, FileBasedKeystore};
 use sui_sdk::types::crypto::Signature;
 use serde_json::Value;
 use shared_crypto::intent::Intent;
 use sui_sdk::types::{
-    base_types::{ObjectID, SuiAddress},
+    base_types::{ObjectID, ObjectRef, SuiAddress},
     quorum_driver_types::ExecuteTransactionRequestType,
-    transaction::{Transaction, TransactionData},
+    transaction::{Transaction, TransactionData, TransactionDataAPI},
 };
 
 use sui_sdk::{rpc_types::SuiTransactionBlockResponseOptions, SuiClient};
 
+use crate::router::Route;
+use crate::signer::Signer;
+use crate::workload::SwapSpec;
 use std::path::PathBuf;
+use std::sync::Arc;
 use sui_move_build::BuildConfig;
 
+// Gas budget of each pre-signed transaction
+pub const MOVE_CALL_GAS_BUDGET: u64 = 190000000;
+
 // The struct that send transactions
 #[derive(Clone)]
 pub struct DataAndSender
 {
-    pub sig: Signature,
+    // None until it is signed with a coin of a gas pool, when it is sent
+    pub sig: Option<Signature>,
     pub test_sender: TestTransactionSender,
     pub tx_data: TransactionData,
+    // the index of the shard (contract) the transaction is sent to
+    pub shard: usize,
+    // when to send it (seconds since the start of the run), None to follow the arrival process
+    pub send_at: Option<f64>,
+    // the interface function it calls, e.g. "swap" or "add_liquidity"
+    pub operation: &'static str,
+    // the candidates of the other shards when a router picks the shard at submission time
+    pub route: Option<Route>,
+    // the swap it makes, None for the other operations
+    pub swap: Option<SwapSpec>,
 }
 
 impl DataAndSender
 {
     pub fn new(sig: Signature, test_sender: TestTransactionSender, tx_data: TransactionData) -> Self {
+        let mut data = Self::unsigned(test_sender, tx_data);
+        data.sig = Some(sig);
+        data
+    }
+    pub fn unsigned(test_sender: TestTransactionSender, tx_data: TransactionData) -> Self {
         Self {
-            sig,
+            sig: None,
             test_sender,
-            tx_data
+            tx_data,
+            shard: 0,
+            send_at: None,
+            operation: "swap",
+            route: None,
+            swap: None,
         }
     }    
+    // Pay the gas with this version of a coin instead of the gas object it was built with, and sign it
+    pub fn sign_with_gas(&mut self, gas: ObjectRef) -> Result<(), anyhow::Error>
+    {
+        let sender = self.test_sender.sender;
+        self.tx_data = TransactionData::new(self.tx_data.kind().clone(), sender, gas, self.tx_data.gas_budget(), self.tx_data.gas_price());
+        self.test_sender.gas_object = gas.0;
+        self.sign()
+    }
+    // Sign the transaction as it was built
+    pub fn sign(&mut self) -> Result<(), anyhow::Error>
+    {
+        let sender = self.test_sender.sender;
+        self.sig = Some(self.test_sender.signer.sign(&sender, &self.tx_data)?);
+        Ok(())
+    }
     pub async fn submit_tx(self) -> Result<SuiTransactionBlockResponse, anyhow::Error>
     {
-        Ok(self.test_sender.submit_tx(self.sig, self.tx_data).await?)
+        let sig = self.sig.ok_or_else(|| anyhow::anyhow!("The transaction was not signed"))?;
+        Ok(self.test_sender.submit_tx(sig, self.tx_data).await?)
     }
 }
 
@@ -52,6 +92,8 @@ pub struct TestTransactionSender {
     pub sender: SuiAddress,
     pub gas_object: ObjectID,
     pub client: SuiClient,
+    // holds the keys of sender, shared by all senders
+    pub signer: Arc<Signer>,
 }
 
 struct PublishData {
@@ -81,6 +123,7 @@ impl TestTransactionSender{
             sender,
             gas_object,
             client,
+            signer: Signer::shared(),
         }
     }
     // Submit the transaction and its signature
@@ -98,6 +141,14 @@ impl TestTransactionSender{
     }
     // a move call with signed transaction (not sent)
     pub async fn move_call_before_submit(self, package_object_id:ObjectID, module:&str, function: &str, type_args: Vec<SuiTypeTag>, call_args: Vec<SuiJsonValue>) -> Result<DataAndSender, anyhow::Error>
+    {
+        let tx_data = self.move_call_before_sign(package_object_id, module, function, type_args, call_args).await?;
+        let signature = self.signer.sign(&self.sender, &tx_data)?;
+        Ok(DataAndSender::new(signature, self, tx_data))
+    }
+
+    // a move call not signed yet, e.g. to sign many of them with Signer::sign_batch
+    pub async fn move_call_before_sign(&self, package_object_id:ObjectID, module:&str, function: &str, type_args: Vec<SuiTypeTag>, call_args: Vec<SuiJsonValue>) -> Result<TransactionData, anyhow::Error>
     {
         let args = call_args
             .into_iter()
@@ -110,17 +161,14 @@ impl TestTransactionSender{
             .collect::<Result<Vec<_>, _>>()?;        
         let tx_data = self.client
         .transaction_builder()
-        .move_call(self.sender, package_object_id, module, function, type_args, args, Some(self.gas_object), 190000000)
+        .move_call(self.sender, package_object_id, module, function, type_args, args, Some(self.gas_object), MOVE_CALL_GAS_BUDGET)
         .await?;
-        let keystore = FileBasedKeystore::new(&sui_config_dir()?.join(SUI_KEYSTORE_FILENAME))?;
-        let signature: Signature = keystore.sign_secure(&self.sender, &tx_data, Intent::sui_transaction())?;
-        Ok(DataAndSender::new(signature, self.clone(), tx_data))
+        Ok(tx_data)
     }
 
     pub async fn sign_and_send(self, tx_data: TransactionData) -> Result<SuiTransactionBlockResponse, anyhow::Error>
     {
-        let keystore = FileBasedKeystore::new(&sui_config_dir()?.join(SUI_KEYSTORE_FILENAME))?;
-        let signature: Signature = keystore.sign_secure(&self.sender, &tx_data, Intent::sui_transaction())?;
+        let signature = self.signer.sign(&self.sender, &tx_data)?;
 
         let transaction_response  = self.client
             .quorum_driver_api()
@@ -146,7 +194,7 @@ impl TestTransactionSender{
     }
 
     // publish the move package
-    pub async fn publish_package(self, package_str: &'static str) -> Result<SuiTransactionBlockResponse, anyhow::Error>
+    pub async fn publish_package(self, package_str: &str) -> Result<SuiTransactionBlockResponse, anyhow::Error>
     {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         path.extend(["..", package_str]);
@@ -0,0 +1,105 @@
+use crate::config::ExperimentConfig;
+use crate::execution::ExperimentReturn;
+use crate::search::{Search, StepSummary};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+// One finished repetition of the sweep
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CellRecord
+{
+    pub group: usize,
+    pub num_shards: usize,
+    pub tps: usize,
+    pub repetition: usize,
+    pub result: ExperimentReturn,
+}
+
+// Position of a sweep, rewritten after every finished cell so that an interrupted run can be resumed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Checkpoint
+{
+    pub config: ExperimentConfig,
+    // the min_tps of the next group (raised while the latency stays low)
+    pub min_tps: usize,
+    // the group in progress
+    pub group: usize,
+    // the first expected TPS of the group in progress
+    pub group_start_tps: usize,
+    // the search of the expected TPS in this group
+    pub search: Search,
+    // the steps of this group finished so far
+    pub steps: Vec<StepSummary>,
+    pub cells: Vec<CellRecord>,
+}
+
+impl Checkpoint
+{
+    pub fn new(config: ExperimentConfig) -> Self
+    {
+        let min_tps = config.min_tps;
+        let search = Search::new(&config, min_tps);
+        Self {
+            config,
+            min_tps,
+            group: 0,
+            group_start_tps: min_tps,
+            search,
+            steps: vec![],
+            cells: vec![],
+        }
+    }
+
+    // Move to the given group, starting from the current min_tps
+    pub fn start_group(&mut self, group: usize)
+    {
+        self.group = group;
+        self.group_start_tps = self.min_tps;
+        self.search = Search::new(&self.config, self.min_tps);
+        self.steps.clear();
+    }
+
+    pub fn path(folder: &Path) -> PathBuf
+    {
+        folder.join(CHECKPOINT_FILE)
+    }
+
+    pub fn load(folder: &Path) -> Result<Self, anyhow::Error>
+    {
+        let path = Self::path(folder);
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Cannot read checkpoint {}: {}", path.display(), e))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    // Write to a temporary file first so that a crash never leaves a truncated checkpoint
+    pub fn save(&self, folder: &Path) -> Result<(), anyhow::Error>
+    {
+        let path = Self::path(folder);
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    // The result of a finished cell, if any
+    pub fn finished(&self, group: usize, tps: usize, repetition: usize) -> Option<&ExperimentReturn>
+    {
+        self.cells.iter()
+            .find(|cell| cell.group == group && cell.tps == tps && cell.repetition == repetition)
+            .map(|cell| &cell.result)
+    }
+
+    pub fn record(&mut self, group: usize, num_shards: usize, tps: usize, repetition: usize, result: ExperimentReturn)
+    {
+        self.cells.push(CellRecord {
+            group,
+            num_shards,
+            tps,
+            repetition,
+            result,
+        });
+    }
+}
@@ -0,0 +1,466 @@
+use crate::arrival::ArrivalProcess;
+use crate::router::{RouterConfig, RoutingPolicy};
+use crate::search::{SearchStrategy, Slo};
+use crate::stopping::{default_stop_rules, RaiseMinTps, StopRule};
+use crate::workload::{OperationMix, ShardPopularity, SwapMix, TraceConfig};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// Default test times (seconds)
+pub const DEFAULT_TIME_WARM_UP: f64 = 500.0;
+pub const DEFAULT_TIME_TEST: f64 = 100.0;
+pub const DEFAULT_TIME_COOL_DOWN: f64 = 50.0;
+// Default width of the buckets of the time series (seconds)
+pub const DEFAULT_TIME_BUCKET: f64 = 1.0;
+
+// Default locations of the sui binaries and of the local network
+pub const DEFAULT_VALIDATOR_BIN: &str = "../../sui/target/release/sui-test-validator";
+pub const DEFAULT_SUI_BIN: &str = "../../sui/target/release/sui";
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:9000";
+pub const DEFAULT_FAUCET_URL: &str = "http://127.0.0.1:9123/gas";
+pub const DEFAULT_OUTPUT_DIR: &str = ".";
+pub const DEFAULT_LEDGER_DIR: &str = "suilog";
+
+
+/// Command line of the benchmark. Every flag overrides the same key of the experiment file;
+/// workload parameters missing from both are asked on stdin (unless --non-interactive).
+#[derive(Parser, Debug, Default)]
+#[command(about = "Throughput and latency benchmark of AMMs on a local sui-test-validator")]
+pub struct Cli
+{
+    /// Experiment spec file (TOML)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Resume the interrupted sweep stored in this result folder (its saved configuration is used)
+    #[arg(long)]
+    pub resume: Option<PathBuf>,
+    /// Fail instead of prompting when a parameter is missing
+    #[arg(long)]
+    pub non_interactive: bool,
+    /// Number of trader clients
+    #[arg(long)]
+    pub num_clients: Option<usize>,
+    /// Number of funded accounts the trader clients sign with, 1 for the active address (default: one per client)
+    #[arg(long)]
+    pub accounts: Option<usize>,
+    /// Minimal expected TPS of the first group
+    #[arg(long)]
+    pub min_tps: Option<usize>,
+    /// Maximal expected TPS
+    #[arg(long)]
+    pub max_tps: Option<usize>,
+    /// Increase of the expected TPS after each step
+    #[arg(long)]
+    pub tps_interval: Option<usize>,
+    /// Repetitions of each expected TPS
+    #[arg(long)]
+    pub num_repeat: Option<usize>,
+    /// Number of shards of each group, e.g. --num-shards 1,2,4
+    #[arg(long, value_delimiter = ',')]
+    pub num_shards: Option<Vec<usize>>,
+    /// Warm-up time in seconds
+    #[arg(long)]
+    pub time_warm_up: Option<f64>,
+    /// Measured test time in seconds
+    #[arg(long)]
+    pub time_test: Option<f64>,
+    /// Cool-down time in seconds
+    #[arg(long)]
+    pub time_cool_down: Option<f64>,
+    /// Width of the buckets of the per-run time series in seconds
+    #[arg(long)]
+    pub time_bucket: Option<f64>,
+    /// Folder in which the timestamped result folder is created
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+    /// Path of the sui-test-validator binary
+    #[arg(long)]
+    pub validator_bin: Option<PathBuf>,
+    /// Path of the sui binary (used for genesis)
+    #[arg(long)]
+    pub sui_bin: Option<PathBuf>,
+    /// Ledger folder of sui-test-validator, wiped by every genesis
+    #[arg(long)]
+    pub ledger_dir: Option<PathBuf>,
+    /// Fullnode JSON-RPC URL
+    #[arg(long)]
+    pub rpc_url: Option<String>,
+    /// Faucet URL
+    #[arg(long)]
+    pub faucet_url: Option<String>,
+    /// Seed of the random arrivals (a random seed per run if not given)
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Also write every transaction of a run to transactions.csv
+    #[arg(long)]
+    pub tx_records: bool,
+    /// Do not read the pools after each run to reconcile them with the successful swaps
+    #[arg(long)]
+    pub no_audit: bool,
+    /// Build every pre-signed transaction through the fullnode instead of locally
+    #[arg(long)]
+    pub online_build: bool,
+    /// Start every run from the same genesis and reuse a saved setup when the planned workload matches
+    #[arg(long)]
+    pub reuse_setup: bool,
+    /// Send the workload of the first repetition of each expected TPS in every repetition, reusing its setup (needs --reuse-setup)
+    #[arg(long)]
+    pub share_workload: bool,
+    /// Pay the gas of each client with this many reused coins, signing each transaction when it is sent
+    #[arg(long)]
+    pub gas_coins: Option<usize>,
+    /// How the expected TPS of each group is searched
+    #[arg(long, value_enum)]
+    pub search: Option<SearchStrategy>,
+    /// Latency percentile of the SLO, e.g. 0.99
+    #[arg(long)]
+    pub slo_percentile: Option<f64>,
+    /// Maximal latency at the SLO percentile in seconds
+    #[arg(long)]
+    pub slo_max_latency: Option<f64>,
+    /// Minimal ratio of successful to expected transactions of the SLO
+    #[arg(long)]
+    pub slo_min_success_ratio: Option<f64>,
+    /// Fraction of XBTC->USDT swaps, the rest being USDT->XBTC (default 1)
+    #[arg(long, alias = "x-to-y-ratio")]
+    pub xbtc_to_usdt_ratio: Option<f64>,
+    /// Spread the generated transactions over the shards with a Zipf popularity of this exponent
+    #[arg(long)]
+    pub zipf_s: Option<f64>,
+    /// How the trader clients pick the shard of each swap when they send it
+    #[arg(long, value_enum)]
+    pub routing: Option<RoutingPolicy>,
+    /// Replay the swaps of this trace file (CSV: timestamp,direction,amount[,pool]) at the expected TPS
+    #[arg(long)]
+    pub trace: Option<PathBuf>,
+}
+
+/// Content of the experiment file. All keys are optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExperimentSpec
+{
+    pub num_clients: Option<usize>,
+    pub accounts: Option<usize>,
+    pub min_tps: Option<usize>,
+    pub max_tps: Option<usize>,
+    pub tps_interval: Option<usize>,
+    pub num_repeat: Option<usize>,
+    pub num_shards: Option<Vec<usize>>,
+    pub time_warm_up: Option<f64>,
+    pub time_test: Option<f64>,
+    pub time_cool_down: Option<f64>,
+    pub time_bucket: Option<f64>,
+    pub output_dir: Option<PathBuf>,
+    pub validator_bin: Option<PathBuf>,
+    pub sui_bin: Option<PathBuf>,
+    pub ledger_dir: Option<PathBuf>,
+    pub rpc_url: Option<String>,
+    pub faucet_url: Option<String>,
+    pub seed: Option<u64>,
+    pub tx_records: Option<bool>,
+    pub audit: Option<bool>,
+    pub offline_build: Option<bool>,
+    pub reuse_setup: Option<bool>,
+    pub share_workload: Option<bool>,
+    pub gas_coins: Option<usize>,
+    pub search: Option<SearchStrategy>,
+    pub slo: Option<Slo>,
+    pub stop_rules: Option<Vec<StopRule>>,
+    pub raise_min_tps: Option<RaiseMinTps>,
+    pub arrival: Option<ArrivalProcess>,
+    pub trace: Option<TraceConfig>,
+    pub swaps: Option<SwapMix>,
+    pub operations: Option<OperationMix>,
+    pub shard_popularity: Option<ShardPopularity>,
+    pub router: Option<RouterConfig>,
+}
+impl ExperimentSpec
+{
+    pub fn from_file(path: &PathBuf) -> Result<Self, anyhow::Error>
+    {
+        let content = std::fs::read_to_string(path)?;
+        let spec = toml::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid experiment file {}: {}", path.display(), e))?;
+        Ok(spec)
+    }
+}
+
+/// The resolved configuration of an experiment
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExperimentConfig
+{
+    pub num_clients: usize,
+    // client c signs with account c % accounts: the active address alone, or accounts keys generated for the sweep
+    #[serde(default = "default_accounts")]
+    pub accounts: usize,
+    pub min_tps: usize,
+    pub max_tps: usize,
+    pub tps_interval: usize,
+    pub num_repeat: usize,
+    pub num_shards: Vec<usize>,
+    pub time_warm_up: f64,
+    pub time_test: f64,
+    pub time_cool_down: f64,
+    #[serde(default = "default_time_bucket")]
+    pub time_bucket: f64,
+    pub output_dir: PathBuf,
+    pub validator_bin: PathBuf,
+    pub sui_bin: PathBuf,
+    #[serde(default = "default_ledger_dir")]
+    pub ledger_dir: PathBuf,
+    pub rpc_url: String,
+    pub faucet_url: String,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub tx_records: bool,
+    // read the pools before and after each run and reconcile them with the successful swaps
+    #[serde(default = "default_true")]
+    pub audit: bool,
+    // build the pre-signed transactions locally from object references read in batches
+    #[serde(default = "default_true")]
+    pub offline_build: bool,
+    // every run starts from the genesis pinned by the first one, and from the ledger saved after the last
+    // setup when it planned the same workload
+    #[serde(default)]
+    pub reuse_setup: bool,
+    // the repetitions of an expected TPS send the workload of the first one, so that they reuse its setup;
+    // they are then not independent samples of the workload
+    #[serde(default)]
+    pub share_workload: bool,
+    // the gas coins of each client, reused from one transaction to the next; None pre-signs every
+    // transaction with a gas coin of its own
+    #[serde(default)]
+    pub gas_coins: Option<usize>,
+    #[serde(default)]
+    pub search: SearchStrategy,
+    #[serde(default)]
+    pub slo: Slo,
+    // rules ending each group, checked in order after every expected TPS
+    #[serde(default = "default_stop_rules")]
+    pub stop_rules: Vec<StopRule>,
+    #[serde(default)]
+    pub raise_min_tps: RaiseMinTps,
+    // when the clients send their transactions
+    #[serde(default)]
+    pub arrival: ArrivalProcess,
+    // replay a trace instead of generating the swaps
+    #[serde(default)]
+    pub trace: Option<TraceConfig>,
+    // direction and size of the generated swaps
+    #[serde(default)]
+    pub swaps: SwapMix,
+    // share of swaps and liquidity operations in the generated workload
+    #[serde(default)]
+    pub operations: OperationMix,
+    // how the generated transactions are spread over the shards of a group
+    #[serde(default)]
+    pub shard_popularity: ShardPopularity,
+    // how the clients pick the shard of each swap when they send it
+    #[serde(default)]
+    pub router: RouterConfig,
+}
+
+fn default_true() -> bool
+{
+    true
+}
+
+// configurations saved when every client signed with the active address
+fn default_accounts() -> usize
+{
+    1
+}
+
+fn default_ledger_dir() -> PathBuf
+{
+    PathBuf::from(DEFAULT_LEDGER_DIR)
+}
+
+fn default_time_bucket() -> f64
+{
+    DEFAULT_TIME_BUCKET
+}
+
+fn input_integer(prompt: &str) -> usize {
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).expect("Failed to read line");
+
+    input.trim().parse().unwrap_or_else(|_| {
+        println!("Please enter a valid number!");
+        input_integer(prompt)
+    })
+}
+
+// Take the value from the command line, then the experiment file, then stdin
+fn resolve_integer(cli: Option<usize>, spec: Option<usize>, name: &str, interactive: bool) -> Result<usize, anyhow::Error>
+{
+    match cli.or(spec) {
+        Some(value) => Ok(value),
+        None if interactive => Ok(input_integer(&format!("Please input {}: ", name))),
+        None => Err(anyhow::anyhow!("Missing parameter {} (use --{} or the experiment file)", name, name.replace('_', "-"))),
+    }
+}
+
+impl ExperimentConfig
+{
+    pub fn resolve(cli: Cli) -> Result<Self, anyhow::Error>
+    {
+        let spec = match &cli.config {
+            Some(path) => ExperimentSpec::from_file(path)?,
+            None => ExperimentSpec::default(),
+        };
+        let interactive = !cli.non_interactive;
+
+        let num_clients = resolve_integer(cli.num_clients, spec.num_clients, "num_clients", interactive)?;
+        let min_tps = resolve_integer(cli.min_tps, spec.min_tps, "min_tps", interactive)?;
+        let max_tps = resolve_integer(cli.max_tps, spec.max_tps, "max_tps", interactive)?;
+        let tps_interval = resolve_integer(cli.tps_interval, spec.tps_interval, "tps_interval", interactive)?;
+        let num_repeat = resolve_integer(cli.num_repeat, spec.num_repeat, "num_repeat", interactive)?;
+        let num_shards = match cli.num_shards.or(spec.num_shards) {
+            Some(num_shards) => num_shards,
+            None => {
+                let num_groups = resolve_integer(None, None, "num_groups", interactive)?;
+                (0..num_groups)
+                    .map(|i| input_integer(&format!("Please input the number of contract in group {}: ", i)))
+                    .collect()
+            }
+        };
+        if num_clients == 0 || tps_interval == 0 || num_repeat == 0 || min_tps == 0 {
+            return Err(anyhow::anyhow!("num_clients, min_tps, tps_interval and num_repeat must be positive"));
+        }
+        if min_tps > max_tps {
+            return Err(anyhow::anyhow!("min_tps ({}) is above max_tps ({})", min_tps, max_tps));
+        }
+        let accounts = cli.accounts.or(spec.accounts).unwrap_or(num_clients);
+        if accounts == 0 || accounts > num_clients {
+            return Err(anyhow::anyhow!("accounts must be between 1 and num_clients"));
+        }
+        let gas_coins = cli.gas_coins.or(spec.gas_coins);
+        if gas_coins == Some(0) {
+            return Err(anyhow::anyhow!("gas_coins must be positive"));
+        }
+        if num_shards.iter().any(|n| *n == 0) {
+            return Err(anyhow::anyhow!("Every group needs at least one shard"));
+        }
+        let reuse_setup = cli.reuse_setup || spec.reuse_setup.unwrap_or(false);
+        let share_workload = cli.share_workload || spec.share_workload.unwrap_or(false);
+        if share_workload && !reuse_setup {
+            return Err(anyhow::anyhow!("share_workload needs reuse_setup"));
+        }
+        let time_warm_up = cli.time_warm_up.or(spec.time_warm_up).unwrap_or(DEFAULT_TIME_WARM_UP);
+        let time_test = cli.time_test.or(spec.time_test).unwrap_or(DEFAULT_TIME_TEST);
+        let time_cool_down = cli.time_cool_down.or(spec.time_cool_down).unwrap_or(DEFAULT_TIME_COOL_DOWN);
+        if !time_test.is_finite() || time_test <= 0.0 {
+            return Err(anyhow::anyhow!("time_test must be positive"));
+        }
+        if !time_warm_up.is_finite() || time_warm_up < 0.0 || !time_cool_down.is_finite() || time_cool_down < 0.0 {
+            return Err(anyhow::anyhow!("time_warm_up and time_cool_down must be zero or positive"));
+        }
+        let trace = match (cli.trace, spec.trace) {
+            (Some(path), Some(trace)) => Some(TraceConfig { path, ..trace }),
+            (Some(path), None) => Some(TraceConfig { path, amount_scale: 1.0 }),
+            (None, trace) => trace,
+        };
+        if trace.as_ref().map_or(false, |trace| !(trace.amount_scale.is_finite() && trace.amount_scale > 0.0)) {
+            return Err(anyhow::anyhow!("The amount_scale of the trace must be positive"));
+        }
+        let time_bucket = cli.time_bucket.or(spec.time_bucket).unwrap_or(DEFAULT_TIME_BUCKET);
+        if time_bucket <= 0.0 {
+            return Err(anyhow::anyhow!("time_bucket must be positive"));
+        }
+        let mut slo = spec.slo.unwrap_or_default();
+        slo.percentile = cli.slo_percentile.unwrap_or(slo.percentile);
+        slo.max_latency = cli.slo_max_latency.unwrap_or(slo.max_latency);
+        slo.min_success_ratio = cli.slo_min_success_ratio.unwrap_or(slo.min_success_ratio);
+        if !(slo.percentile > 0.0 && slo.percentile <= 1.0) {
+            return Err(anyhow::anyhow!("The SLO percentile must be in (0, 1]"));
+        }
+        let stop_rules = spec.stop_rules.unwrap_or_else(default_stop_rules);
+        if stop_rules.iter().any(|rule| matches!(rule, StopRule::PercentileLatency { percentile, .. } if !(*percentile > 0.0 && *percentile <= 1.0))) {
+            return Err(anyhow::anyhow!("The percentile of a stopping rule must be in (0, 1]"));
+        }
+        let arrival = spec.arrival.unwrap_or_default();
+        arrival.validate()?;
+        let mut swaps = spec.swaps.unwrap_or_default();
+        swaps.xbtc_to_usdt_ratio = cli.xbtc_to_usdt_ratio.unwrap_or(swaps.xbtc_to_usdt_ratio);
+        swaps.validate()?;
+        let operations = spec.operations.unwrap_or_default();
+        operations.validate()?;
+        let shard_popularity = match cli.zipf_s {
+            Some(s) => ShardPopularity::Zipf { s },
+            None => spec.shard_popularity.unwrap_or_default(),
+        };
+        shard_popularity.validate(&num_shards)?;
+        let mut router = spec.router.unwrap_or_default();
+        router.policy = cli.routing.unwrap_or(router.policy);
+        if router.refresh_interval <= 0.0 {
+            return Err(anyhow::anyhow!("The refresh_interval of the router must be positive"));
+        }
+
+        Ok(Self {
+            num_clients,
+            accounts,
+            min_tps,
+            max_tps,
+            tps_interval,
+            num_repeat,
+            num_shards,
+            time_warm_up,
+            time_test,
+            time_cool_down,
+            time_bucket,
+            output_dir: cli.output_dir.or(spec.output_dir).unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT_DIR)),
+            validator_bin: cli.validator_bin.or(spec.validator_bin).unwrap_or_else(|| PathBuf::from(DEFAULT_VALIDATOR_BIN)),
+            sui_bin: cli.sui_bin.or(spec.sui_bin).unwrap_or_else(|| PathBuf::from(DEFAULT_SUI_BIN)),
+            ledger_dir: cli.ledger_dir.or(spec.ledger_dir).unwrap_or_else(default_ledger_dir),
+            rpc_url: cli.rpc_url.or(spec.rpc_url).unwrap_or_else(|| DEFAULT_RPC_URL.to_string()),
+            faucet_url: cli.faucet_url.or(spec.faucet_url).unwrap_or_else(|| DEFAULT_FAUCET_URL.to_string()),
+            seed: cli.seed.or(spec.seed),
+            tx_records: cli.tx_records || spec.tx_records.unwrap_or(false),
+            audit: !cli.no_audit && spec.audit.unwrap_or(true),
+            offline_build: !cli.online_build && spec.offline_build.unwrap_or(true),
+            reuse_setup,
+            share_workload,
+            gas_coins,
+            search: cli.search.or(spec.search).unwrap_or_default(),
+            slo,
+            stop_rules,
+            raise_min_tps: spec.raise_min_tps.unwrap_or_default(),
+            arrival,
+            trace,
+            swaps,
+            operations,
+            shard_popularity,
+            router,
+        })
+    }
+
+    // The seed of one run: derived from the configured seed, or random
+    pub fn run_seed(&self, group: usize, tps: usize, repetition: usize) -> u64
+    {
+        match self.seed {
+            Some(seed) => seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(((group as u64) << 40) ^ ((tps as u64) << 16) ^ repetition as u64),
+            None => rand::random(),
+        }
+    }
+
+    pub fn num_groups(&self) -> usize
+    {
+        self.num_shards.len()
+    }
+
+    // The resolved configuration in the experiment file format, for info.txt
+    pub fn to_toml(&self) -> Result<String, anyhow::Error>
+    {
+        Ok(toml::to_string(self)?)
+    }
+}
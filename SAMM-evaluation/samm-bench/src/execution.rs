@@ -0,0 +1,523 @@
+use crate::build_tx::{DataAndSender, TestTransactionSender};
+use crate::backend::MoveCallSpec;
+use crate::arrival::{ArrivalProcess, Arrivals, RateProfile};
+use crate::config::ExperimentConfig;
+use crate::router::Router;
+use crate::audit::{AuditReport, PoolAudit};
+use crate::gas_pool::GasPool;
+use crate::build_tx::MOVE_CALL_GAS_BUDGET;
+use crate::workload::SwapSpec;
+use crate::metrics::{load_imbalance, steady_state, time_series, LatencyHistogram, LatencySummary, OperationResult, ShardResult, SteadyState};
+use crate::failure::{Failure, FailureClass};
+use crate::results::{write_audit, write_client_file, write_histogram, write_shard_results, write_time_series, TxRecordWriter, TIME_SERIES_FILE, TX_RECORDS_FILE};
+use anyhow::Ok;
+use tokio;
+use sui_json_rpc_types::SuiTransactionBlockResponse;
+use tokio::time::sleep;
+use sui_json_rpc_types::{SuiExecutionStatus, SuiTransactionBlockEffectsAPI};
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+use sui_sdk::types::transaction::TransactionData;
+use sui_sdk::SuiClient;
+use std::time::{SystemTime, Duration};
+
+use std::path::PathBuf;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// The result of each execution
+#[derive(Clone)]
+pub struct ExecutionReturn
+{
+    // whether the execution is successful
+    pub if_sucess: usize,
+    // the start time of the execution
+    pub start_time: f64,
+    // the end time of the execution
+    pub end_time: f64,
+    // the index of the shard (contract) the transaction was sent to
+    pub shard: usize,
+    // the digest of the transaction
+    pub digest: String,
+    // why the transaction failed, None on success
+    pub failure: Option<Failure>,
+    // the interface function called, empty when unknown (e.g. read back from a client file)
+    pub operation: &'static str,
+    // the swap it made, None for the other operations or when unknown
+    pub swap: Option<SwapSpec>,
+    // the account that signed it, None when unknown
+    pub sender: Option<SuiAddress>,
+}
+impl ExecutionReturn{
+    pub fn new(if_sucess: usize, start_time: f64, end_time: f64, shard: usize, digest: String, failure: Option<Failure>, operation: &'static str) -> Self {
+        Self {
+            if_sucess,
+            start_time,
+            end_time,
+            shard,
+            digest,
+            failure,
+            operation,
+            swap: None,
+            sender: None,
+        }
+    }
+}
+
+// The result of the whole experiment
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExperimentReturn
+{
+    // the number of successful transactions
+    pub success: usize,
+    // the number of failed transactions
+    pub fail: usize,
+    // mean and percentiles of the latency of successful transactions
+    pub latency: LatencySummary,
+    // the latency distribution of successful transactions
+    pub histogram: LatencyHistogram,
+    // the number of failed transactions of each failure class
+    #[serde(default)]
+    pub failures: BTreeMap<String, usize>,
+    // the same metrics for each shard
+    #[serde(default)]
+    pub shards: Vec<ShardResult>,
+    // max/mean of the transactions sent to each shard
+    #[serde(default)]
+    pub load_imbalance: Option<f64>,
+    // whether the test window was stationary, judged on the time series of the run
+    #[serde(default)]
+    pub steady_state: Option<SteadyState>,
+    // whether sui-test-validator exited during the run
+    #[serde(default)]
+    pub validator_crashed: bool,
+    // the same counts and latencies for each kind of operation (swap, add_liquidity...)
+    #[serde(default)]
+    pub operations: BTreeMap<String, OperationResult>,
+    // the pools read after the run, reconciled with the successful swaps
+    #[serde(default)]
+    pub audit: Option<AuditReport>,
+    // the gas coins retired from the gas pools of the clients, None without gas pools
+    #[serde(default)]
+    pub gas_coins_retired: Option<usize>,
+    // the requests the router sent to read the reserves of the pools, None without routing
+    #[serde(default)]
+    pub router_rpcs: Option<usize>,
+}
+impl ExperimentReturn
+{
+    pub fn new(success: usize, fail: usize, latency: LatencySummary, histogram: LatencyHistogram, failures: BTreeMap<String, usize>) -> Self 
+    {
+        Self {
+            success,
+            fail,
+            latency,
+            histogram,
+            failures,
+            shards: vec![],
+            load_imbalance: None,
+            steady_state: None,
+            validator_crashed: false,
+            operations: BTreeMap::new(),
+            audit: None,
+            gas_coins_retired: None,
+            router_rpcs: None,
+        }
+    }
+
+    // the average latency of successful transactions, None if nothing succeeded
+    pub fn average_latency(&self) -> Option<f64>
+    {
+        self.latency.mean
+    }
+}
+
+// Generate the signed transaction (not submitted)
+pub async fn call_swap_before_submit(client: SuiClient, sender: SuiAddress, gas_obj: ObjectID, swap_call: MoveCallSpec, shard: usize, operation: &'static str)
+    -> Result<DataAndSender, anyhow::Error>
+{
+    let test_transaction_sender = TestTransactionSender::new(sender, gas_obj, client);
+    let mut data_from_response = 
+    test_transaction_sender.move_call_before_submit(swap_call.package, swap_call.module, swap_call.function, swap_call.type_args, swap_call.call_args).await?;
+    data_from_response.shard = shard;
+    data_from_response.operation = operation;
+    Ok(data_from_response)
+}
+
+// Generate the transaction without signing it, to be signed in a batch
+pub async fn call_swap_before_sign(client: SuiClient, sender: SuiAddress, gas_obj: ObjectID, swap_call: MoveCallSpec)
+    -> Result<(TestTransactionSender, TransactionData), anyhow::Error>
+{
+    let test_transaction_sender = TestTransactionSender::new(sender, gas_obj, client);
+    let tx_data = test_transaction_sender
+        .move_call_before_sign(swap_call.package, swap_call.module, swap_call.function, swap_call.type_args, swap_call.call_args).await?;
+    Ok((test_transaction_sender, tx_data))
+}
+
+
+
+
+// The latency of a transaction starts when it is sent, once it is signed. With a gas pool, the transaction
+// waits for a free coin of its client and is signed with it: its latency includes the wait and the signature.
+// A routed swap picked among the unsigned candidates is signed on the blocking pool before its latency starts,
+// so that the routing policies are timed like the pre-signed transactions of the fixed one
+async fn call_swap_new(execution: DataAndSender, origin_time: SystemTime, gas_pool: Option<Arc<GasPool>>)
+    -> Result<ExecutionReturn, anyhow::Error>
+{
+    let shard = execution.shard;
+    let operation = execution.operation;
+    let swap = execution.swap.clone();
+    let sender = execution.test_sender.sender;
+    let (mut execution, signed) = if execution.sig.is_none() && gas_pool.is_none() {
+        tokio::task::spawn_blocking(move || {
+            let mut execution = execution;
+            let signed = execution.sign();
+            (execution, signed)
+        }).await?
+    } else {
+        (execution, Ok(()))
+    };
+    let start_time = SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+    let signed = match (&gas_pool, signed) {
+        (Some(pool), _) => pool.acquire().await.and_then(|coin| {
+            execution.sign_with_gas(coin.object_ref).map(|()| Some(coin)).map_err(|e| {
+                pool.put_back(coin);
+                e
+            })
+        }),
+        (None, signed) => signed.map(|()| None),
+    };
+    let gas = match signed {
+        Ok(gas) => gas,
+        Err(e) => {
+            let end_time = SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+            let failure = Failure::new(FailureClass::Client, format!("not signed: {:#}", e));
+            let mut execution_return = ExecutionReturn::new(0, start_time, end_time, shard, String::new(), Some(failure), operation);
+            execution_return.sender = Some(sender);
+            return Ok(execution_return);
+        }
+    };
+    let digest = execution.tx_data.digest().to_string();
+
+
+    let transaction_response_result: Result<sui_json_rpc_types::SuiTransactionBlockResponse, anyhow::Error> = execution.submit_tx().await;
+    let mut failure = None;
+    let transaction_response = transaction_response_result.unwrap_or_else(|err| {
+        failure = Some(Failure::submit_error(format!("{:#}", err)));
+        SuiTransactionBlockResponse::default()
+    });
+    let mut if_success = 0;
+    match transaction_response.effects.as_ref().map(|effects| effects.status())
+    {
+        Some(SuiExecutionStatus::Success) => {
+            if_success = 1;
+        }
+        Some(SuiExecutionStatus::Failure { error }) => {
+            failure = Some(Failure::execution_error(error.clone()));
+        }
+        None => {
+            if failure.is_none()
+            {
+                failure = Some(Failure::new(FailureClass::Rpc, "no effects returned".to_string()));
+            }
+        }
+    }
+
+    let end_time = SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+    if let (Some(pool), Some(coin)) = (&gas_pool, gas)
+    {
+        pool.release(coin, transaction_response.effects.as_ref()).await;
+    }
+    
+    let mut execution_return = ExecutionReturn::new(if_success,start_time,end_time,shard,digest,failure,operation);
+    execution_return.swap = swap;
+    execution_return.sender = Some(sender);
+    Ok(execution_return)
+}
+
+// The task of a single client
+async fn execution_single_new(expected_interval: f64,
+    execution_list:Vec<DataAndSender>, time3: f64, origin_time: SystemTime, folder_path: PathBuf, id: usize, seed: u64, profile: Arc<RateProfile>,
+    router: Option<Arc<Router>>, gas_pool: Option<Arc<GasPool>>)
+-> Result<Vec<ExecutionReturn>, anyhow::Error>
+{
+    let mut results = vec![];
+    let lambda = 1.0 / expected_interval; 
+    let mut tasks = vec![];
+    // each client draws its arrivals from the seed of the run
+    let rng = StdRng::seed_from_u64(seed.wrapping_add(id as u64));
+    let mut arrivals = Arrivals::new(lambda, profile, rng);
+    let mut route_rng = StdRng::seed_from_u64(seed.wrapping_add(id as u64) ^ 0x726f_7574_6572);
+
+    let mut st = SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+    for mut execution in execution_list
+    {
+        // a replayed transaction waits for its own time
+        if let Some(send_at) = execution.send_at
+        {
+            if send_at > time3
+            {
+                break
+            }
+            let rest_time = send_at - SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+            if rest_time > 0.0
+            {
+                sleep(Duration::from_secs_f64(rest_time)).await;
+            }
+        }
+        let scheduled = execution.send_at.is_some();
+        // a routed swap goes to the shard the router picks now, or stays on its planned shard
+        if let (Some(router), Some(route)) = (&router, execution.route.take())
+        {
+            if let Some(shard) = router.choose(&route.swap, &mut route_rng)
+            {
+                if let Some(candidate) = route.candidates.into_iter().nth(shard)
+                {
+                    execution = candidate;
+                }
+            }
+        }
+        // use a thread to call the function
+        let gas_pool = gas_pool.clone();
+        let task= tokio::spawn(async move {
+            call_swap_new(execution, origin_time, gas_pool).await
+            });
+        tasks.push(task);
+        if scheduled
+        {
+            continue;
+        }
+        // sleep until the next arrival of the arrival process
+        let next_time = arrivals.next_arrival(st);
+        if next_time > time3
+        {
+            break
+        }
+        let rest_time = next_time - SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+        if rest_time > 0.0
+        {
+            let duration = Duration::from_secs_f64(rest_time);
+            sleep(duration).await;
+        }
+        st = SystemTime::now().duration_since(origin_time)?.as_secs_f64();
+    }
+    for tmp_task in tasks
+    {
+        results.push(tmp_task.await.unwrap_or_else(|err| 
+            {
+                Ok(ExecutionReturn::new(0, 0.0, 0.0, 0, String::new(), Some(Failure::new(FailureClass::Client, format!("task failed: {}", err))), ""))
+            }).unwrap());
+    }
+    write_client_file(&folder_path, id, &results)?;
+    Ok(results)
+}
+
+// Reduce the executions sent inside the test window [time1, time2] to the metrics of the experiment,
+// in total and for each of the num_shards shards
+pub fn summarize_results<'a>(results: impl IntoIterator<Item = &'a ExecutionReturn>, time1: f64, time2: f64, num_shards: usize) -> ExperimentReturn
+{
+    let mut latencies = vec![];
+    let mut histogram = LatencyHistogram::new();
+    let mut success = 0;
+    let mut fail = 0;
+    let mut failures = BTreeMap::new();
+    let mut shards: Vec<ShardResult> = (0..num_shards).map(|shard| ShardResult { shard, ..Default::default() }).collect();
+    let mut shard_latencies: Vec<Vec<f64>> = vec![vec![]; num_shards];
+    let mut operations: BTreeMap<String, OperationResult> = BTreeMap::new();
+    let mut operation_latencies: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for res in results
+    {
+        if !(res.start_time > time1 && res.start_time < time2)
+        {
+            continue;
+        }
+        if res.shard >= shards.len()
+        {
+            shards.resize_with(res.shard + 1, Default::default);
+            shard_latencies.resize(res.shard + 1, vec![]);
+        }
+        let shard = &mut shards[res.shard];
+        shard.shard = res.shard;
+        shard.submitted += 1;
+        let operation = if res.operation.is_empty() {
+            None
+        } else {
+            Some(operations.entry(res.operation.to_string()).or_default())
+        };
+        if res.if_sucess == 1
+        {
+            success += 1;
+            latencies.push(res.end_time - res.start_time);
+            histogram.record(res.end_time - res.start_time);
+            shard.success += 1;
+            shard_latencies[res.shard].push(res.end_time - res.start_time);
+            if let Some(operation) = operation
+            {
+                operation.success += 1;
+                operation_latencies.entry(res.operation.to_string()).or_default().push(res.end_time - res.start_time);
+            }
+        }
+        else
+        {
+            fail += 1;
+            if let Some(operation) = operation
+            {
+                operation.fail += 1;
+            }
+            let label = res.failure.as_ref().map(|failure| failure.class.label()).unwrap_or_else(|| "unknown".to_string());
+            *failures.entry(label.clone()).or_insert(0) += 1;
+            shard.fail += 1;
+            *shard.failures.entry(label).or_insert(0) += 1;
+        }
+    }
+    for (shard, samples) in shards.iter_mut().zip(shard_latencies.iter())
+    {
+        shard.throughput = shard.success as f64 / (time2 - time1);
+        shard.latency = LatencySummary::from_samples(samples);
+    }
+    for (name, operation) in operations.iter_mut()
+    {
+        operation.latency = LatencySummary::from_samples(operation_latencies.get(name).map_or(&[][..], |samples| samples));
+    }
+    let latency = LatencySummary::from_samples(&latencies);
+    let mut result = ExperimentReturn::new(success,fail,latency,histogram,failures);
+    result.operations = operations;
+    result.load_imbalance = load_imbalance(&shards);
+    result.shards = shards;
+    result
+}
+
+// The knobs of a run besides its rate and times: those of the experiment, built once from its config,
+// and those the harness sets before each run
+pub struct RunOptions
+{
+    // write every transaction to transactions.csv
+    pub tx_records: bool,
+    // width in seconds of the buckets of the time series
+    pub time_bucket: f64,
+    pub arrival: ArrivalProcess,
+    // sign each transaction when it is sent, with a coin of the gas pool of its client
+    pub gas_pool: bool,
+    // seed of the arrivals of the run
+    pub seed: u64,
+    // routes the swaps over the shards, when set
+    pub router: Option<Arc<Router>>,
+    // the pools before the run, reconciled with its transactions
+    pub audit: Option<PoolAudit>,
+}
+
+impl RunOptions
+{
+    pub fn new(config: &ExperimentConfig) -> Self
+    {
+        Self {
+            tx_records: config.tx_records,
+            time_bucket: config.time_bucket,
+            arrival: config.arrival.clone(),
+            gas_pool: config.gas_coins.is_some(),
+            seed: 0,
+            router: None,
+            audit: None,
+        }
+    }
+}
+
+// One client per queue of execution_list_total
+pub async fn execution_pool_new(expected_interval: f64, client: SuiClient, execution_list_total: Vec<Vec<DataAndSender>>,
+    time_warm_up: f64, time_cool_down: f64, time_test: f64, writen_path: PathBuf, options: &RunOptions)
+    -> Result<ExperimentReturn, anyhow::Error>
+{
+    let seed = options.seed;
+    let router = options.router.clone();
+    let num_shards = execution_list_total.iter().flatten().map(|execution| execution.shard + 1).max().unwrap_or(1)
+        .max(router.as_ref().map_or(0, |router| router.globals.len()));
+    let time1 = time_warm_up;
+    let time2 = time1 + time_test;
+    let time3 = time2 + time_cool_down;
+    // shared by all clients, so that bursts are simultaneous
+    let profile = Arc::new(options.arrival.profile(seed, time3));
+    // the gas coins of each client, read before the run
+    let gas_pools: Vec<Option<Arc<GasPool>>> = if options.gas_pool {
+        GasPool::for_clients(&client, &execution_list_total, MOVE_CALL_GAS_BUDGET).await?.into_iter().map(Some).collect()
+    } else {
+        vec![None; execution_list_total.len()]
+    };
+    // the routed swaps are sent from the reserves read at least once
+    if let Some(router) = &router
+    {
+        if let Err(e) = router.refresh(&client).await
+        {
+            eprintln!("Failed to read the pool reserves: {}", e);
+        }
+    }
+    let origin_time = SystemTime::now();
+    let refresher = router.clone().map(|router| router.spawn_refresher(client.clone(), origin_time, time3));
+    let mut tasks = Vec::new();
+    let mut id = 0;
+    // Each client is spawned in a separate task
+    for (execution_list, gas_pool) in execution_list_total.into_iter().zip(gas_pools.iter().cloned())
+    {
+        let writen_path_clone = writen_path.clone();
+        let profile = profile.clone();
+        let router = router.clone();
+        let task= tokio::spawn(async move {
+            execution_single_new(expected_interval, execution_list, time3, origin_time, writen_path_clone, id, seed, profile, router, gas_pool).await
+            });
+        tasks.push(task);
+        id += 1;
+    }
+
+    let mut tx_writer = if options.tx_records {
+        Some(TxRecordWriter::create(&writen_path.join(TX_RECORDS_FILE))?)
+    } else {
+        None
+    };
+    let mut all_results = vec![];
+    for (client_id, task) in tasks.into_iter().enumerate()
+    {
+        //let result_part = task.await?;
+        let result_part = task.await??;
+        for res in result_part
+        {
+            if let Some(writer) = tx_writer.as_mut()
+            {
+                writer.write(client_id, &res, time1, time2)?;
+            }
+            all_results.push(res);
+        }
+    }
+
+    if let Some(writer) = tx_writer.as_mut()
+    {
+        writer.flush()?;
+    }
+    if let Some(refresher) = refresher
+    {
+        refresher.abort();
+    }
+    let gas_coins_retired: usize = gas_pools.iter().flatten().map(|pool| pool.retired()).sum();
+
+    let mut result = summarize_results(&all_results, time1, time2, num_shards);
+    write_histogram(&writen_path, &result.histogram)?;
+    write_shard_results(&writen_path, &result.shards)?;
+    // The whole run, warm-up and cool-down included, bucket by bucket
+    let series = time_series(&all_results, options.time_bucket);
+    write_time_series(&writen_path.join(TIME_SERIES_FILE), &series)?;
+    result.steady_state = Some(steady_state(&series, options.time_bucket, time1, time2));
+    if options.gas_pool
+    {
+        result.gas_coins_retired = Some(gas_coins_retired);
+    }
+    result.router_rpcs = router.as_ref().map(|router| router.rpcs());
+    // Every transaction of the run, warm-up and cool-down included, changed the pools
+    if let Some(audit) = &options.audit
+    {
+        let report = audit.reconcile(&client, &all_results).await;
+        write_audit(&writen_path, &report)?;
+        result.audit = Some(report);
+    }
+
+    Ok(result)
+}
\ No newline at end of file
@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Why a transaction did not succeed. Classes are recognised from the messages of the
+// quorum driver / JSON-RPC errors and from the execution status in the effects.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureClass
+{
+    // the RPC request or the quorum driver timed out before finality
+    RpcTimeout,
+    // an owned object was locked by, or already used at this version in, another transaction (equivocation)
+    ObjectConflict,
+    // the gas coin could not pay for the transaction
+    InsufficientGas,
+    // the Move code aborted, e.g. implements::ERR_COIN_OUT_NUM_LESS_THAN_EXPECTED_MINIMUM
+    MoveAbort { module: String, code: u64 },
+    // validators or the fullnode refused the transaction because they are overloaded
+    Overload,
+    // any other error while submitting the transaction
+    Rpc,
+    // any other failed execution status
+    Execution,
+    // the client task itself failed
+    Client,
+}
+
+// A failed transaction: its class and the original error message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Failure
+{
+    pub class: FailureClass,
+    pub message: String,
+}
+
+// Abort codes of the swap::implements, swap::interface and swap::math modules
+pub fn abort_code_name(module: &str, code: u64) -> Option<&'static str>
+{
+    let name = match (module, code) {
+        ("implements", 0) => "ERR_ZERO_AMOUNT",
+        ("implements", 1) => "ERR_RESERVES_EMPTY",
+        ("implements", 2) => "ERR_POOL_FULL",
+        ("implements", 3) => "ERR_INSUFFICIENT_COIN_X",
+        ("implements", 4) => "ERR_INSUFFICIENT_COIN_Y",
+        ("implements", 5) => "ERR_DIVIDE_BY_ZERO",
+        ("implements", 6) => "ERR_OVERLIMIT",
+        ("implements", 7) => "ERR_COIN_OUT_NUM_LESS_THAN_EXPECTED_MINIMUM",
+        ("implements", 8) => "ERR_LIQUID_NOT_ENOUGH",
+        ("implements", 9) => "ERR_THE_SAME_COIN",
+        ("implements", 10) => "ERR_POOL_HAS_REGISTERED",
+        ("implements", 11) => "ERR_POOL_NOT_REGISTER",
+        ("implements", 12) => "ERR_MUST_BE_ORDER",
+        ("implements", 13) => "ERR_U64_OVERFLOW",
+        ("implements", 14) => "ERR_INCORRECT_SWAP",
+        ("implements", 15) => "ERR_INSUFFICIENT_LIQUIDITY_MINTED",
+        ("interface", 101) => "ERR_NO_PERMISSIONS",
+        ("interface", 102) => "ERR_EMERGENCY",
+        ("interface", 103) => "ERR_GLOBAL_MISMATCH",
+        ("interface", 104) => "ERR_UNEXPECTED_RETURN",
+        ("interface", 105) => "ERR_EMPTY_COINS",
+        ("math", 500) => "ERR_DIVIDE_BY_ZERO",
+        ("math", 501) => "ERR_U64_OVERFLOW",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// "MoveAbort(MoveLocation { module: ModuleId { address: .., name: Identifier(\"implements\") }, .. }, 7) in command 0"
+pub fn parse_move_abort(message: &str) -> Option<FailureClass>
+{
+    let rest = &message[message.find("MoveAbort(")?..];
+    let end = rest.find(") in command").or_else(|| rest.rfind(')'))?;
+    let inner = &rest[..end];
+    let code = inner.rsplit(',').next()?.trim().parse().ok()?;
+    let module = inner.find("name: Identifier(\"")
+        .map(|start| {
+            let name = &inner[start + "name: Identifier(\"".len()..];
+            name[..name.find('"').unwrap_or(name.len())].to_string()
+        })
+        .unwrap_or_default();
+    Some(FailureClass::MoveAbort { module, code })
+}
+
+// The lowercase words of a message. Digests and object ids are single words, so a status code or a
+// keyword is only matched when it stands alone, not inside an id
+struct Words(Vec<String>);
+
+impl Words
+{
+    fn new(message: &str) -> Self
+    {
+        Self(message.split(|c: char| !c.is_ascii_alphanumeric()).filter(|word| !word.is_empty()).map(str::to_lowercase).collect())
+    }
+
+    // Whether the words of phrase appear in a row; a word ending with * matches any word it starts
+    fn has(&self, phrase: &str) -> bool
+    {
+        let phrase: Vec<&str> = phrase.split(' ').collect();
+        self.0.windows(phrase.len()).any(|window| {
+            window.iter().zip(&phrase).all(|(word, expected)| match expected.strip_suffix('*') {
+                Some(prefix) => word.starts_with(prefix),
+                None => word == expected,
+            })
+        })
+    }
+
+    fn has_any(&self, phrases: &[&str]) -> bool
+    {
+        phrases.iter().any(|phrase| self.has(phrase))
+    }
+}
+
+impl FailureClass
+{
+    // Classify the error returned when submitting a transaction. The specific causes are checked
+    // before the overload, whose words are the most generic
+    pub fn from_submit_error(message: &str) -> Self
+    {
+        let words = Words::new(message);
+        if let Some(class) = parse_move_abort(message)
+        {
+            class
+        }
+        else if words.has_any(&["equivocat*", "locked", "lockconflict*", "objectsdoubleused*", "doubleused*", "double used",
+            "not available for consumption", "objectversionunavailableforconsumption*"])
+        {
+            FailureClass::ObjectConflict
+        }
+        else if words.has_any(&["timed out", "timeout", "timeoutbeforefinality*"])
+        {
+            FailureClass::RpcTimeout
+        }
+        else if words.has_any(&["insufficientgas*", "insufficient gas", "gasbalancetoolow*", "gas balance", "lower than the needed amount"])
+        {
+            FailureClass::InsufficientGas
+        }
+        else if words.has_any(&["overload*", "systemoverload*", "validatoroverloaded*", "too many requests", "toomanytransactions*",
+            "429", "server is busy", "system busy"])
+        {
+            FailureClass::Overload
+        }
+        else
+        {
+            FailureClass::Rpc
+        }
+    }
+
+    // Classify a failed execution status of the effects
+    pub fn from_execution_error(message: &str) -> Self
+    {
+        if let Some(class) = parse_move_abort(message)
+        {
+            class
+        }
+        else if message.contains("InsufficientGas")
+        {
+            FailureClass::InsufficientGas
+        }
+        else if message.contains("InputObjectDeleted") || message.contains("SharedObjectOperationNotAllowed")
+        {
+            FailureClass::ObjectConflict
+        }
+        else
+        {
+            FailureClass::Execution
+        }
+    }
+
+    // Short label used in raw files and in the failure breakdown
+    pub fn label(&self) -> String
+    {
+        match self {
+            FailureClass::RpcTimeout => "rpc_timeout".to_string(),
+            FailureClass::ObjectConflict => "object_conflict".to_string(),
+            FailureClass::InsufficientGas => "insufficient_gas".to_string(),
+            FailureClass::MoveAbort { module, code } => match abort_code_name(module, *code) {
+                Some(name) => format!("move_abort:{}:{}:{}", module, code, name),
+                None => format!("move_abort:{}:{}", module, code),
+            },
+            FailureClass::Overload => "overload".to_string(),
+            FailureClass::Rpc => "rpc".to_string(),
+            FailureClass::Execution => "execution".to_string(),
+            FailureClass::Client => "client".to_string(),
+        }
+    }
+
+    // Inverse of label(), None for unknown labels
+    pub fn from_label(label: &str) -> Option<Self>
+    {
+        let class = match label {
+            "rpc_timeout" => FailureClass::RpcTimeout,
+            "object_conflict" => FailureClass::ObjectConflict,
+            "insufficient_gas" => FailureClass::InsufficientGas,
+            "overload" => FailureClass::Overload,
+            "rpc" => FailureClass::Rpc,
+            "execution" => FailureClass::Execution,
+            "client" => FailureClass::Client,
+            _ => {
+                let mut parts = label.strip_prefix("move_abort:")?.split(':');
+                let module = parts.next()?.to_string();
+                let code = parts.next()?.parse().ok()?;
+                FailureClass::MoveAbort { module, code }
+            }
+        };
+        Some(class)
+    }
+}
+
+impl fmt::Display for FailureClass
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl Failure
+{
+    pub fn new(class: FailureClass, message: String) -> Self
+    {
+        Self {
+            class,
+            message,
+        }
+    }
+
+    pub fn submit_error(message: String) -> Self
+    {
+        Self::new(FailureClass::from_submit_error(&message), message)
+    }
+
+    pub fn execution_error(message: String) -> Self
+    {
+        Self::new(FailureClass::from_execution_error(&message), message)
+    }
+}
@@ -6,10 +6,25 @@ use sui_sdk::types::base_types::ObjectID;
 use tokio;
 use std::time::Duration;
 use tokio::time::sleep;
+use std::sync::OnceLock;
 
 pub const SUI_FAUCET: &str = "http://127.0.0.1:9123/gas";
 pub const MAX_PROCESS: usize = 100;
 
+// Faucet used by all requests, set once from the experiment configuration
+static FAUCET_URL: OnceLock<String> = OnceLock::new();
+
+pub fn set_faucet_url(url: &str)
+{
+    // The URL cannot change during an experiment, later calls are ignored
+    let _ = FAUCET_URL.set(url.to_string());
+}
+
+fn faucet_url() -> &'static str
+{
+    FAUCET_URL.get().map(|url| url.as_str()).unwrap_or(SUI_FAUCET)
+}
+
 // Extract id from a json file
 fn extract_ids(json_body: &Value) -> Vec<String> {
     let mut ids = Vec::new();
@@ -39,7 +54,7 @@ pub async fn request_five_gas_obj(
     // make the request to the faucet JSON RPC API for coin
     let client = Client::new();
     let resp = client
-        .post(SUI_FAUCET)
+        .post(faucet_url())
         .header("Content-Type", "application/json")
         .json(&json_body)
         .send()
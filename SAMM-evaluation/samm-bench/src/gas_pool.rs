@@ -0,0 +1,174 @@
+use crate::build_tx::DataAndSender;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use sui_json_rpc_types::{SuiObjectDataOptions, SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI};
+use sui_sdk::types::base_types::{ObjectID, ObjectRef, SuiAddress};
+use sui_sdk::SuiClient;
+use tokio::sync::Semaphore;
+
+// A gas coin of a pool: its last known version and a lower bound of its balance
+#[derive(Clone, Copy, Debug)]
+pub struct GasCoin
+{
+    pub object_ref: ObjectRef,
+    pub balance: u64,
+}
+
+struct PoolState
+{
+    free: Vec<GasCoin>,
+    // coins paying for a transaction that has not returned yet
+    in_flight: usize,
+    retired: usize,
+}
+
+// The gas coins of one client, each reused by its next transaction as soon as the effects of the
+// previous one return the new version of the coin
+pub struct GasPool
+{
+    client: SuiClient,
+    // a coin is retired when its balance may not cover this budget
+    gas_budget: u64,
+    state: Mutex<PoolState>,
+    // one permit per free coin, closed when no coin is left
+    available: Semaphore,
+}
+
+impl GasPool
+{
+    pub fn new(client: SuiClient, coins: Vec<GasCoin>, gas_budget: u64) -> Self
+    {
+        let available = Semaphore::new(coins.len());
+        if coins.is_empty()
+        {
+            available.close();
+        }
+        Self {
+            client,
+            gas_budget,
+            state: Mutex::new(PoolState { free: coins, in_flight: 0, retired: 0 }),
+            available,
+        }
+    }
+
+    // The pool of each client, holding the gas objects its queue was built with
+    pub async fn for_clients(client: &SuiClient, queues: &[Vec<DataAndSender>], gas_budget: u64) -> Result<Vec<Arc<GasPool>>, anyhow::Error>
+    {
+        let mut coins_of: HashMap<SuiAddress, HashMap<ObjectID, GasCoin>> = HashMap::new();
+        let mut pools = vec![];
+        for (c, queue) in queues.iter().enumerate()
+        {
+            let mut coins = vec![];
+            if let Some(sender) = queue.first().map(|tx| tx.test_sender.sender)
+            {
+                if !coins_of.contains_key(&sender)
+                {
+                    coins_of.insert(sender, read_gas_coins(client, sender).await?);
+                }
+                let mut ids: Vec<ObjectID> = queue.iter().map(|tx| tx.test_sender.gas_object).collect();
+                ids.sort();
+                ids.dedup();
+                for id in ids
+                {
+                    let coin = coins_of[&sender].get(&id)
+                        .ok_or_else(|| anyhow::anyhow!("The gas coin {} of client {} is not a SUI coin of {}", id, c, sender))?;
+                    coins.push(*coin);
+                }
+            }
+            pools.push(Arc::new(GasPool::new(client.clone(), coins, gas_budget)));
+        }
+        Ok(pools)
+    }
+
+    // Wait for a free coin
+    pub async fn acquire(&self) -> Result<GasCoin, anyhow::Error>
+    {
+        self.available.acquire().await
+            .map_err(|_| anyhow::anyhow!("Every gas coin of the client was retired"))?
+            .forget();
+        let mut state = self.state.lock().unwrap();
+        let coin = state.free.pop().ok_or_else(|| anyhow::anyhow!("The gas pool has a permit without a coin"))?;
+        state.in_flight += 1;
+        Ok(coin)
+    }
+
+    // Give back a coin that paid for nothing, e.g. when the transaction could not be signed
+    pub fn put_back(&self, coin: GasCoin)
+    {
+        self.finish(Some(coin));
+    }
+
+    // Give back the coin that paid for a transaction, at the version of its effects. Without effects
+    // the transaction may still execute: the coin is reused only if a newer version can be read, and
+    // retired otherwise, since signing another transaction with a locked version would fail
+    pub async fn release(&self, coin: GasCoin, effects: Option<&SuiTransactionBlockEffects>)
+    {
+        let next = match effects {
+            Some(effects) => {
+                let spent = effects.gas_cost_summary().net_gas_usage();
+                Some(GasCoin {
+                    object_ref: effects.gas_object().reference.to_object_ref(),
+                    balance: (coin.balance as i64 - spent).max(0) as u64,
+                })
+            }
+            None => self.refresh(coin).await,
+        };
+        self.finish(next.filter(|next| next.balance >= self.gas_budget));
+    }
+
+    // The coin at its current version, if a transaction executed with it
+    async fn refresh(&self, coin: GasCoin) -> Option<GasCoin>
+    {
+        let (id, version, _) = coin.object_ref;
+        let response = self.client.read_api().get_object_with_options(id, SuiObjectDataOptions::new()).await.ok()?;
+        let object_ref = response.data?.object_ref();
+        // the transaction spent at most its budget
+        (object_ref.1 > version).then(|| GasCoin { object_ref, balance: coin.balance.saturating_sub(self.gas_budget) })
+    }
+
+    fn finish(&self, coin: Option<GasCoin>)
+    {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight -= 1;
+        match coin {
+            Some(coin) => {
+                state.free.push(coin);
+                self.available.add_permits(1);
+            }
+            None => {
+                state.retired += 1;
+                if state.free.is_empty() && state.in_flight == 0
+                {
+                    self.available.close();
+                }
+            }
+        }
+    }
+
+    // The coins taken out of the pool: spent below the gas budget or lost with a transaction
+    pub fn retired(&self) -> usize
+    {
+        self.state.lock().unwrap().retired
+    }
+}
+
+// The SUI coins of address, by id
+async fn read_gas_coins(client: &SuiClient, address: SuiAddress) -> Result<HashMap<ObjectID, GasCoin>, anyhow::Error>
+{
+    let mut coins = HashMap::new();
+    let mut cursor = None;
+    loop
+    {
+        let page = client.coin_read_api().get_coins(address, None, cursor, None).await?;
+        for coin in page.data
+        {
+            coins.insert(coin.coin_object_id, GasCoin { object_ref: coin.object_ref(), balance: coin.balance });
+        }
+        if !page.has_next_page
+        {
+            break;
+        }
+        cursor = page.next_cursor;
+    }
+    Ok(coins)
+}
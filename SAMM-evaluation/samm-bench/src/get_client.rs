@@ -25,10 +25,10 @@ use sui_sdk::{SuiClient, SuiClientBuilder};
 /// and ensures that the active address of the wallet has SUI on it.
 /// If there is no SUI owned by the active address, then it will request
 /// SUI from the faucet.
-pub async fn client_info() -> Result<(SuiClient, SuiAddress), anyhow::Error> {
+pub async fn client_info(rpc_url: &str) -> Result<(SuiClient, SuiAddress), anyhow::Error> {
     // let client = SuiClientBuilder::default().build_testnet().await?;
 
-    let client = SuiClientBuilder::default().max_concurrent_requests(500_000).build_localnet().await?;
+    let client = SuiClientBuilder::default().max_concurrent_requests(500_000).build(rpc_url).await?;
     // let client = SuiClientBuilder::default().max_concurrent_requests(500_000).build("http://132.68.60.223:9200").await?;
 
     
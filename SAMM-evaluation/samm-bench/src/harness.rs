@@ -0,0 +1,535 @@
+use crate::faucet::{get_gas_obj_one_layer, set_faucet_url};
+use crate::get_client::client_info;
+use crate::config::{Cli, ExperimentConfig};
+use crate::checkpoint::Checkpoint;
+use crate::search::{KneeReport, StepSummary};
+use clap::Parser;
+use crate::build_contract::{amm_data_builder, fund_trader_accounts, TraderAccount};
+use crate::workload::{uniform_plan, Trace, MAX_SWAP_AMOUNT};
+use crate::router::{Router, RoutingPolicy};
+use crate::audit::PoolAudit;
+use crate::setup_cache::{setup_fingerprint, SetupCache, SETUP_CACHE_DIR};
+use crate::signer::Signer;
+use crate::model::Pricing;
+use crate::backend::AmmBackend;
+use crate::execution::{execution_pool_new, ExperimentReturn, RunOptions};
+use crate::metrics::{fmt_opt, SteadyState};
+use crate::results::{RunRecord, RESULT_SCHEMA_VERSION};
+use tokio::time::Duration;
+
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+use std::io::Write;
+use chrono::Local;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use tokio::time::sleep;
+
+fn start_command(config: &ExperimentConfig) -> process::Child {
+    process::Command::new(&config.validator_bin)
+        .env("RUST_LOG", "consensus=off")
+        .arg("--config-dir")
+        .arg(&config.ledger_dir)
+        .arg("--epoch-duration-ms")
+        .arg("999999999")
+        .spawn()
+        .expect("Failed to start command")
+}
+
+// A new genesis in the ledger folder. With a setup cache, the first genesis is pinned and every later one
+// restores it, so that all runs start from the same keys and genesis objects
+fn genesis(config: &ExperimentConfig, pinned: Option<&SetupCache>) -> Result<(), anyhow::Error>
+{
+    if let Some(cache) = pinned
+    {
+        if cache.restore_genesis(&config.ledger_dir)?
+        {
+            println!("Genesis restored from {}", cache.dir.display());
+            return Ok(());
+        }
+    }
+    let output1 = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("rm -rf '{}'/*", config.ledger_dir.display()))
+        .output()
+        .expect("failed to execute process");
+
+    if output1.status.success() {
+        println!("Files deleted successfully");
+    } else {
+        eprintln!("Error deleting files");
+    }
+    let output2 = process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} genesis -f --with-faucet --working-dir='{}'", config.sui_bin.display(), config.ledger_dir.display()))
+        .output()
+        .expect("failed to execute process");
+
+    if output2.status.success() {
+        println!("Genesis successful");
+        if let Some(cache) = pinned
+        {
+            cache.pin_genesis(&config.ledger_dir)?;
+        }
+    } else {
+        eprintln!("Error genesis!");
+    }
+    Ok(())
+}
+
+// The version reported by sui-test-validator, recorded with every run
+fn validator_version(config: &ExperimentConfig) -> String
+{
+    match process::Command::new(&config.validator_bin).arg("--version").output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+async fn reset_env(config: &ExperimentConfig, pinned: Option<&SetupCache>) -> Result<process::Child, anyhow::Error>
+{
+    start_env(config, &|config| genesis(config, pinned)).await
+}
+
+// Start a sui-test-validator on the ledger folder prepared by `prepare` (a new genesis, a restored setup, or nothing to restart)
+async fn start_env(config: &ExperimentConfig, prepare: &dyn Fn(&ExperimentConfig) -> Result<(), anyhow::Error>) -> Result<process::Child, anyhow::Error>
+{
+    prepare(config)?;
+    let duration = Duration::from_secs_f64(5.0);
+    let mut sui_test_validator_process = start_command(config);
+    loop
+    {
+        sleep(duration).await;
+        match sui_test_validator_process.try_wait()
+        {
+            Ok(Some(status)) => {
+                println!("Sui-test-validator failed ({}), restart!", status);
+                prepare(config)?;
+                sui_test_validator_process = start_command(config);
+            }
+            Ok(None) => {
+                println!("Sui-test-validator normally started, proceed!");
+                break;
+            }
+            Err(e) => {
+                println!("Fail to detect status ({}), try again!", e);
+            }
+        }
+    }
+
+    sleep(duration).await;
+    Ok(sui_test_validator_process)
+}
+
+
+// Run the whole experiment against the given AMM, configured from the command line
+pub async fn run<B: AmmBackend>(backend: B) -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+    if let Some(folder) = cli.resume.clone() {
+        return resume(backend, folder).await;
+    }
+    let config = ExperimentConfig::resolve(cli)?;
+    run_with_config(backend, config).await
+}
+
+// Start a new sweep of shards and TPS as described by the configuration
+pub async fn run_with_config<B: AmmBackend>(backend: B, config: ExperimentConfig) -> Result<(), anyhow::Error> {
+    let folder_name = config.output_dir.join(Local::now().format("%Y-%m-%d-%H-%M-%S").to_string());
+
+    let mut info_file_path = PathBuf::from(&folder_name);
+    info_file_path.push("info.txt");
+    if let Err(e) = std::fs::create_dir_all(&folder_name) {
+        eprintln!("Failed to create folder: {}", e);
+        return Err(anyhow::Error::msg("Failed to create folder"));
+    }
+    
+    let mut info_file = File::create(&info_file_path)?;
+
+    
+    writeln!(&mut info_file, "The number of clients: {}", config.num_clients)?;
+    writeln!(&mut info_file, "The number of accounts: {}", config.accounts)?;
+    writeln!(
+        &mut info_file,
+        "The min_tps: {}",
+        config.min_tps
+    )?;
+    writeln!(
+        &mut info_file,
+        "The max_tps: {}",
+        config.max_tps
+    )?;
+    writeln!(&mut info_file, "The tps_interval: {}", config.tps_interval)?;
+    writeln!(
+        &mut info_file,
+        "The repeated time: {}",
+        config.num_repeat
+    )?;
+    for ng in config.num_shards.clone() 
+    {
+        writeln!(&mut info_file, "Number of contracts: {}", ng)?;
+    }
+    writeln!(&mut info_file, "AMM: {}", backend.name())?;
+    writeln!(&mut info_file, "\nResolved configuration:\n{}", config.to_toml()?)?;
+
+    let checkpoint = Checkpoint::new(config);
+    checkpoint.save(&folder_name)?;
+    sweep(&backend, &folder_name, checkpoint).await
+}
+
+fn fmt_next_tps(checkpoint: &Checkpoint) -> String
+{
+    match checkpoint.search.next_tps() {
+        Some(tps) => tps.to_string(),
+        None => "none (group finished)".to_string(),
+    }
+}
+
+// Continue an interrupted sweep from the checkpoint in its result folder
+pub async fn resume<B: AmmBackend>(backend: B, folder_name: PathBuf) -> Result<(), anyhow::Error> {
+    let checkpoint = Checkpoint::load(&folder_name)?;
+    println!("Resume {} from group {} at expected TPS {} ({} finished runs)",
+        folder_name.display(), checkpoint.group, fmt_next_tps(&checkpoint), checkpoint.cells.len());
+    let mut info_file = OpenOptions::new().create(true).append(true).open(folder_name.join("info.txt"))?;
+    writeln!(&mut info_file, "Resumed at {} from group {}, expected TPS {}",
+        Local::now().format("%Y-%m-%d-%H-%M-%S"), checkpoint.group, fmt_next_tps(&checkpoint))?;
+    sweep(&backend, &folder_name, checkpoint).await
+}
+
+// What the runs of a sweep share
+struct SweepContext<'a, B: AmmBackend>
+{
+    backend: &'a B,
+    config: &'a ExperimentConfig,
+    folder_name: &'a Path,
+    validator_version: String,
+    trace: Option<Trace>,
+    // the keys of the trader accounts live as long as the sweep; empty with one account
+    trader_addresses: Vec<SuiAddress>,
+    // the genesis pinned by the first run and the last setup saved
+    setup_cache: Option<SetupCache>,
+}
+
+async fn sweep<B: AmmBackend>(backend: &B, folder_name: &Path, mut checkpoint: Checkpoint) -> Result<(), anyhow::Error> {
+    let config = checkpoint.config.clone();
+    let num_repeat = config.num_repeat;
+    let num_groups = config.num_groups();
+    let num_shards = config.num_shards.clone();
+    set_faucet_url(&config.faucet_url);
+    let trace = match &config.trace {
+        Some(trace_config) => {
+            let trace = Trace::load(&trace_config.path)?;
+            let clamped = trace.clamped(trace_config);
+            if clamped > 0
+            {
+                println!("{} swaps of the trace are clamped to [1, {}]", clamped, MAX_SWAP_AMOUNT);
+            }
+            Some(trace)
+        }
+        None => None,
+    };
+    // With one account the clients sign with the active address.
+    // With a seed the keys derive from it, so that a resumed sweep matches the setups it saved
+    let trader_addresses = match config.seed {
+        _ if config.accounts <= 1 => vec![],
+        Some(seed) => Signer::shared().seeded_addresses(config.accounts, seed)?,
+        None => Signer::shared().ephemeral_addresses(config.accounts)?,
+    };
+    let setup_cache = if config.reuse_setup {
+        Some(SetupCache::new(folder_name.join(SETUP_CACHE_DIR)))
+    } else {
+        None
+    };
+    let context = SweepContext {
+        backend,
+        config: &config,
+        folder_name,
+        validator_version: validator_version(&config),
+        trace,
+        trader_addresses,
+        setup_cache,
+    };
+
+    for i in checkpoint.group..num_groups
+    {
+        // path of the result file
+        let mut result_path = PathBuf::from(folder_name);
+        let result_file_name = format!("output{}.txt", num_shards[i].clone());
+        result_path.push(result_file_name);
+        let mut result_file = OpenOptions::new().create(true).append(true).open(&result_path)?;
+        let mut result_raw_folder_path = PathBuf::from(folder_name);
+        result_raw_folder_path.push(format!("raw{}", num_shards[i].clone()));
+        if let Err(e) = std::fs::create_dir_all(&result_raw_folder_path) {
+            eprintln!("Failed to create folder: {}", e);
+            return Err(anyhow::Error::msg("Failed to create folder"));
+        }
+        // We generate all transactions before execution. The interval of transactions is drawn from an exponential distribution. We need some redundant transactions to ensure that the execution time is long enough. we generate multi_factor times transactions for redundency.
+        // With gas pools (gas_coins), the redundant transactions are neither signed nor given a gas coin of their own: only their payment coins are minted.
+        let multi_factor: f64;
+        // According to chernoff bound, with higher tps, the possibility of sending multiple times higher than expected tps is lower
+        let group_start_tps = checkpoint.group_start_tps;
+        if group_start_tps <= 100
+        {
+            multi_factor = 4.0;
+        }
+        else if group_start_tps <= 200
+        {
+            multi_factor = 3.0;
+        }
+        else if group_start_tps <= 300
+        {
+            multi_factor = 2.0;
+        }
+        else 
+        {
+            multi_factor = 1.2;    
+        }
+        // the search chooses the expected tps of each test
+        while let Some(current_frequency) = checkpoint.search.next_tps()
+        {
+            let mut step_results = vec![];
+            for t in 0..num_repeat
+            {
+                // Runs finished before an interruption are not repeated
+                if let Some(result) = checkpoint.finished(i, current_frequency, t)
+                {
+                    println!("Test round {} of {} shards at expected TPS {} already finished, skip!", t, num_shards[i], current_frequency);
+                    step_results.push(result.clone());
+                    continue;
+                }
+                let result = run_repetition(&context, i, current_frequency, t, multi_factor, &mut result_file, &result_raw_folder_path).await?;
+                step_results.push(result.clone());
+                checkpoint.record(i, num_shards[i], current_frequency, t, result);
+                checkpoint.save(folder_name)?;
+            }
+            let step = StepSummary::new(current_frequency, &step_results, config.time_test, &config.slo, &config.stop_rules);
+            println!("Expected TPS {}: p{} latency {}, success ratio {}, SLO {}",
+                current_frequency, config.slo.percentile * 100.0, fmt_opt(step.percentile_latency), step.success_ratio,
+                if step.meets_slo { "met" } else { "violated" });
+            checkpoint.search.observe(&step);
+            checkpoint.steps.push(step);
+            if let Some(raised) = checkpoint.search.raised_min_tps()
+            {
+                checkpoint.min_tps = raised;
+            }
+            if let Some(reason) = checkpoint.search.stop_reason()
+            {
+                println!("{}", reason);
+            }
+            else if let Some(fired) = checkpoint.steps.last().and_then(|step| step.stopped_by.as_ref())
+            {
+                println!("Stopping rule fired: {}", fired.message);
+            }
+            checkpoint.save(folder_name)?;
+        }
+        let knee = KneeReport::new(i, num_shards[i], &config.slo, &checkpoint.search, checkpoint.steps.clone());
+        match knee.knee_tps {
+            Some(tps) => println!("Knee of {} shards: {} TPS (first violation: {})", num_shards[i], tps,
+                knee.first_violation_tps.map(|tps| tps.to_string()).unwrap_or_else(|| "none".to_string())),
+            None => println!("Knee of {} shards: no expected TPS met the SLO", num_shards[i]),
+        }
+        knee.append_to(folder_name)?;
+        // The next group starts from the (possibly raised) min_tps
+        checkpoint.start_group(i + 1);
+        checkpoint.save(folder_name)?;
+    };
+
+    Ok(())
+}
+
+// Repetition t of group i at the expected TPS current_frequency: a validator with the setup of the run, the test,
+// its line in the result file and its raw results in result_raw_folder_path, and its record in the result folder
+async fn run_repetition<B: AmmBackend>(context: &SweepContext<'_, B>, i: usize, current_frequency: usize, t: usize, multi_factor: f64,
+    result_file: &mut File, result_raw_folder_path: &Path) -> Result<ExperimentReturn, anyhow::Error>
+{
+    let &SweepContext { backend, config, folder_name, ref trace, ref trader_addresses, ref setup_cache, .. } = context;
+    let num_clients = config.num_clients;
+    let time_warm_up = config.time_warm_up;
+    let time_test = config.time_test;
+    let time_cool_down = config.time_cool_down;
+    let this_num_contract = config.num_shards[i];
+    // Print the information of the test
+    println!("Start test round: {}", t);
+    println!("Number of client: {}", num_clients);
+    println!("Number of shards: {}", this_num_contract);
+    println!("Expected TPS: {}", current_frequency);
+    let tps_interval = num_clients as f64 / current_frequency as f64;
+    let this_multi_factor = multi_factor / this_num_contract as f64;
+    // bursty arrival processes may send more than the expected TPS on average
+    let arrival_factor = config.arrival.mean_factor(time_warm_up + time_cool_down + time_test).max(1.0);
+    let coin_each_client = (current_frequency as f64 * arrival_factor * this_multi_factor*  (time_warm_up + time_cool_down + time_test) / num_clients as f64).ceil();
+    let seed = config.run_seed(i, current_frequency, t);
+    // with share_workload, every repetition plans the workload of the first one, so that they share its setup
+    let plan_seed = if config.share_workload { config.run_seed(i, current_frequency, 0) } else { seed };
+    // The transactions of each client: replayed from the trace, or coin_each_client operations per contract
+    let plan = match (trace, &config.trace) {
+        (Some(trace), Some(trace_config)) => trace.plan(trace_config, current_frequency, num_clients, this_num_contract,
+            time_warm_up + time_test + time_cool_down),
+        _ => uniform_plan(num_clients, this_num_contract, coin_each_client as usize, plan_seed, &config.shard_popularity,
+            &config.operations, &config.swaps),
+    };
+    let route_swaps = config.router.policy != RoutingPolicy::Fixed;
+    // A setup saved from the pinned genesis for the same workload replaces the setup
+    let addresses = Signer::shared().addresses()?;
+    let fingerprint_of = |genesis: u64| setup_fingerprint(genesis, backend.name(), &addresses, this_num_contract, &plan,
+        route_swaps, config.offline_build, config.gas_coins);
+    let mut fingerprint = match setup_cache {
+        Some(cache) => cache.genesis_fingerprint()?.map(fingerprint_of).transpose()?,
+        None => None,
+    };
+    let cached = setup_cache.as_ref().zip(fingerprint).filter(|(cache, fingerprint)| cache.matches(*fingerprint));
+    let mut sui_test_validator_process = match cached {
+        Some((cache, _)) => start_env(config, &|_| cache.restore_ledger(&config.ledger_dir)).await?,
+        None => reset_env(config, setup_cache.as_ref()).await?,
+    };
+    // the first genesis has just been pinned
+    if let (Some(cache), None) = (setup_cache, fingerprint)
+    {
+        fingerprint = cache.genesis_fingerprint()?.map(fingerprint_of).transpose()?;
+    }
+    let (client, active_address) = client_info(&config.rpc_url).await?;
+    let (execution_queue, contracts) = match cached {
+        Some((cache, fingerprint)) => {
+            println!("Reuse the setup saved in {}", cache.dir.display());
+            cache.load(&client, fingerprint)?
+        }
+        None => {
+            // Get the gas object
+            let obj_list = get_gas_obj_one_layer(5, active_address).await?;
+            let coin_str = &obj_list[0];
+            let gas_object_id = coin_str.parse::<ObjectID>()?;
+            let accounts = if trader_addresses.is_empty() {
+                vec![TraderAccount { address: active_address, gas_object: gas_object_id }]
+            } else {
+                fund_trader_accounts(trader_addresses).await?
+            };
+            // Build the smart contracts and the transaction queue
+            let (execution_queue, contracts) = amm_data_builder(backend, client.clone(), active_address, this_num_contract, &plan, gas_object_id,
+                &accounts, config.gas_coins, route_swaps, config.offline_build).await?;
+            if let (Some(cache), Some(fingerprint)) = (setup_cache, fingerprint)
+            {
+                // the ledger is copied while no validator writes it
+                let _ = sui_test_validator_process.kill();
+                let _ = sui_test_validator_process.wait();
+                cache.save(fingerprint, &config.ledger_dir, &contracts, &execution_queue)?;
+                println!("Setup saved in {}", cache.dir.display());
+                sui_test_validator_process = start_env(config, &|_| Ok(())).await?;
+            }
+            (execution_queue, contracts)
+        }
+    };
+    let pricing = Pricing::of_backend(backend.name());
+    let globals: Vec<ObjectID> = contracts.iter().map(|contract| contract.global).collect();
+    let mut run_options = RunOptions::new(config);
+    run_options.seed = seed;
+    if route_swaps
+    {
+        run_options.router = Some(Arc::new(Router::new(&config.router, pricing, globals.clone())));
+    }
+    // the pools as they are after the setup, to be reconciled with the run
+    if config.audit
+    {
+        run_options.audit = Some(PoolAudit::snapshot(&client, pricing, globals).await);
+    }
+    let mut raw_file_path = result_raw_folder_path.to_path_buf();
+    raw_file_path.push(format!("{}-test{}", current_frequency, t));
+    if let Err(e) = std::fs::create_dir_all(&raw_file_path) {
+        eprintln!("Failed to create folder: {}", e);
+        return Err(anyhow::Error::msg("Failed to create folder"));
+    }
+    println!("Execution start!");
+    let started_at = Local::now().to_rfc3339();
+    // Initiate trader clients and start the test
+    let mut result = execution_pool_new(tps_interval, client.clone(), execution_queue, time_warm_up, time_cool_down, time_test, raw_file_path.clone(), &run_options).await?;
+    writeln!(
+        result_file,
+        "{}, {}, {}, {}, 0, {}, {}, {}, {}, {}",
+        current_frequency, result.success, fmt_opt(result.average_latency()), result.fail,
+        fmt_opt(result.latency.p50), fmt_opt(result.latency.p90), fmt_opt(result.latency.p99),
+        fmt_opt(result.latency.p999), fmt_opt(result.latency.max),
+    )?;
+    print_run_summary(config, this_num_contract, current_frequency, t, &result);
+    // A validator that exited on its own crashed during the test
+    result.validator_crashed = matches!(sui_test_validator_process.try_wait(), Ok(Some(_)));
+    if result.validator_crashed
+    {
+        println!("Sui-test-validator crashed during the test!");
+    }
+    match sui_test_validator_process.kill() {
+        Ok(_) => println!("Command terminated."),
+        Err(e) => eprintln!("Failed to terminate command: {}", e),
+    }
+    RunRecord {
+        schema_version: RESULT_SCHEMA_VERSION,
+        amm: backend.name().to_string(),
+        config: config.clone(),
+        group: i,
+        num_shards: this_num_contract,
+        target_tps: current_frequency,
+        repetition: t,
+        seed,
+        validator_version: context.validator_version.clone(),
+        started_at,
+        achieved_tps: result.success as f64 / time_test,
+        metrics: result.clone(),
+    }.append_to(folder_name)?;
+    Ok(result)
+}
+
+// What a finished repetition reports on the console
+fn print_run_summary(config: &ExperimentConfig, num_shards: usize, current_frequency: usize, t: usize, result: &ExperimentReturn)
+{
+    let time_test = config.time_test;
+    println!("Test round: {} finished!", t);
+    println!("Number of client: {}", config.num_clients);
+    println!("Number of shards: {}", num_shards);
+    println!("Expected TPS: {}", current_frequency);
+    println!("Test time: {}", time_test);
+    println!("Number of successful transactions: {}", result.success);
+    println!("True TPS: {}", result.success as f64 / time_test);
+    println!("Number of failed transactions: {}", result.fail);
+    for (class, count) in &result.failures
+    {
+        println!("    {}: {}", class, count);
+    }
+    for shard in &result.shards
+    {
+        println!("Shard {}: {} sent, {} successful, {} failed, average latency {}",
+            shard.shard, shard.submitted, shard.success, shard.fail, fmt_opt(shard.latency.mean));
+    }
+    println!("Load imbalance (max/mean): {}", fmt_opt(result.load_imbalance));
+    if result.operations.len() > 1
+    {
+        for (name, operation) in &result.operations
+        {
+            println!("Operation {}: {} successful, {} failed, average latency {}",
+                name, operation.success, operation.fail, fmt_opt(operation.latency.mean));
+        }
+    }
+    if let Some(audit) = &result.audit
+    {
+        for shard in &audit.shards
+        {
+            if let Some(reason) = &shard.skipped
+            {
+                println!("Audit of shard {}: skipped, {}", shard.shard, reason);
+            }
+            for discrepancy in &shard.discrepancies
+            {
+                println!("Warning: audit of shard {}: {}", shard.shard, discrepancy);
+            }
+        }
+        println!("Audit: {} discrepancies over {} pools", audit.discrepancies(), audit.shards.len());
+    }
+    if let Some(retired) = result.gas_coins_retired.filter(|retired| *retired > 0)
+    {
+        println!("Warning: {} gas coins were retired from the gas pools", retired);
+    }
+    if let Some(SteadyState { reason: Some(reason), .. }) = &result.steady_state
+    {
+        println!("Warning: the test window is not in steady state: {}", reason);
+    }
+    println!("Average latency: {}", fmt_opt(result.average_latency()));
+    println!("Latency p50/p90/p99/p99.9/max: {}/{}/{}/{}/{}", fmt_opt(result.latency.p50), fmt_opt(result.latency.p90),
+        fmt_opt(result.latency.p99), fmt_opt(result.latency.p999), fmt_opt(result.latency.max));
+}
@@ -0,0 +1,29 @@
+// Benchmark harness shared by the test crates of SAMM, heavier SAMM and OmniSwap.
+// Each test crate only chooses the AmmBackend to run.
+pub mod faucet;
+pub mod get_client;
+pub mod build_tx;
+pub mod signer;
+pub mod gas_pool;
+pub mod offline;
+pub mod setup_cache;
+pub mod build_contract;
+pub mod arrival;
+pub mod execution;
+pub mod metrics;
+pub mod failure;
+pub mod results;
+pub mod backend;
+pub mod workload;
+pub mod router;
+pub mod model;
+pub mod audit;
+pub mod config;
+pub mod stopping;
+pub mod search;
+pub mod checkpoint;
+pub mod harness;
+
+pub use backend::{AmmBackend, SammBackend, SammBoostBackend, OmniSwapBackend};
+pub use config::ExperimentConfig;
+pub use harness::{run, run_with_config, resume};
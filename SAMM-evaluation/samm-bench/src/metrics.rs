@@ -0,0 +1,344 @@
+use crate::execution::ExecutionReturn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Values below 2^SUB_BUCKET_BITS microseconds are recorded exactly, larger ones
+// with 2^SUB_BUCKET_BITS buckets per power of two (relative precision better than 1%)
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKET_COUNT: u64 = 1 << SUB_BUCKET_BITS;
+const MICROS_PER_SECOND: f64 = 1_000_000.0;
+
+// Latency histogram with a fixed relative precision (HDR-style), in microseconds.
+// Only non-empty buckets are stored so that it stays small once serialized.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LatencyHistogram
+{
+    pub counts: BTreeMap<u64, u64>,
+    pub total: u64,
+}
+
+fn bucket_index(micros: u64) -> u64
+{
+    if micros < SUB_BUCKET_COUNT
+    {
+        return micros;
+    }
+    let magnitude = 63 - micros.leading_zeros();
+    let shift = magnitude - SUB_BUCKET_BITS;
+    let mantissa = micros >> shift;
+    (shift as u64 + 1) * SUB_BUCKET_COUNT + (mantissa - SUB_BUCKET_COUNT)
+}
+
+// The range [low, high] of microseconds recorded in a bucket
+fn bucket_range(index: u64) -> (u64, u64)
+{
+    if index < SUB_BUCKET_COUNT
+    {
+        return (index, index);
+    }
+    let shift = index / SUB_BUCKET_COUNT - 1;
+    let mantissa = SUB_BUCKET_COUNT + index % SUB_BUCKET_COUNT;
+    let high = (((mantissa + 1) as u128) << shift) - 1;
+    (mantissa << shift, high.min(u64::MAX as u128) as u64)
+}
+
+impl LatencyHistogram
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    // Record a latency in seconds
+    pub fn record(&mut self, latency: f64)
+    {
+        let micros = (latency.max(0.0) * MICROS_PER_SECOND).round() as u64;
+        *self.counts.entry(bucket_index(micros)).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram)
+    {
+        for (index, count) in &other.counts
+        {
+            *self.counts.entry(*index).or_insert(0) += count;
+        }
+        self.total += other.total;
+    }
+
+    pub fn is_empty(&self) -> bool
+    {
+        self.total == 0
+    }
+
+    // (lower bound, upper bound, count) of every non-empty bucket, in seconds
+    pub fn buckets(&self) -> Vec<(f64, f64, u64)>
+    {
+        self.counts.iter()
+            .map(|(index, count)| {
+                let (low, high) = bucket_range(*index);
+                (low as f64 / MICROS_PER_SECOND, high as f64 / MICROS_PER_SECOND, *count)
+            })
+            .collect()
+    }
+
+    // Upper bound of the bucket holding the quantile q (0 < q <= 1), None without samples
+    pub fn value_at_quantile(&self, q: f64) -> Option<f64>
+    {
+        if self.total == 0
+        {
+            return None;
+        }
+        let rank = ((q * self.total as f64).ceil() as u64).clamp(1, self.total);
+        let mut seen = 0;
+        for (index, count) in &self.counts
+        {
+            seen += count;
+            if seen >= rank
+            {
+                return Some(bucket_range(*index).1 as f64 / MICROS_PER_SECOND);
+            }
+        }
+        None
+    }
+}
+
+// Summary of the latencies of successful transactions. Every statistic is None
+// ("no data") when no transaction succeeded in the measured window.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LatencySummary
+{
+    pub count: usize,
+    pub mean: Option<f64>,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+    pub p999: Option<f64>,
+    pub max: Option<f64>,
+}
+
+// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], q: f64) -> Option<f64>
+{
+    if sorted.is_empty()
+    {
+        return None;
+    }
+    let rank = ((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+    Some(sorted[rank - 1])
+}
+
+impl LatencySummary
+{
+    pub fn from_samples(samples: &[f64]) -> Self
+    {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let mean = if sorted.is_empty() {
+            None
+        } else {
+            Some(sorted.iter().sum::<f64>() / sorted.len() as f64)
+        };
+        Self {
+            count: sorted.len(),
+            mean,
+            p50: percentile(&sorted, 0.5),
+            p90: percentile(&sorted, 0.9),
+            p99: percentile(&sorted, 0.99),
+            p999: percentile(&sorted, 0.999),
+            max: sorted.last().copied(),
+        }
+    }
+}
+
+// The share of a run handled by one shard (contract), in the test window
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ShardResult
+{
+    pub shard: usize,
+    // transactions sent to this shard
+    pub submitted: usize,
+    pub success: usize,
+    pub fail: usize,
+    // successful transactions per second
+    pub throughput: f64,
+    pub latency: LatencySummary,
+    pub failures: BTreeMap<String, usize>,
+}
+
+// The share of a run made of one kind of operation, in the test window
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct OperationResult
+{
+    pub success: usize,
+    pub fail: usize,
+    pub latency: LatencySummary,
+}
+
+// Load imbalance across shards: the busiest shard's submitted transactions over the mean.
+// 1.0 means perfectly balanced; None if nothing was submitted.
+pub fn load_imbalance(shards: &[ShardResult]) -> Option<f64>
+{
+    let total: usize = shards.iter().map(|shard| shard.submitted).sum();
+    if shards.is_empty() || total == 0
+    {
+        return None;
+    }
+    let mean = total as f64 / shards.len() as f64;
+    let max = shards.iter().map(|shard| shard.submitted).max().unwrap_or(0);
+    Some(max as f64 / mean)
+}
+
+// Activity of a run during one time bucket. Transactions are counted as submitted in the
+// bucket of their start time and as committed/failed in the bucket of their end time.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimeBucket
+{
+    // start of the bucket, in seconds since the start of the run
+    pub start: f64,
+    pub submitted: usize,
+    pub committed: usize,
+    pub failed: usize,
+    // latency of the transactions committed in this bucket
+    pub mean_latency: Option<f64>,
+    pub p99_latency: Option<f64>,
+}
+
+pub fn time_series<'a>(results: impl IntoIterator<Item = &'a ExecutionReturn>, bucket: f64) -> Vec<TimeBucket>
+{
+    let index = |time: f64| (time.max(0.0) / bucket).floor() as usize;
+    let mut submitted: Vec<usize> = vec![];
+    let mut committed: Vec<usize> = vec![];
+    let mut failed: Vec<usize> = vec![];
+    let mut latencies: Vec<Vec<f64>> = vec![];
+    for res in results
+    {
+        let last = index(res.start_time).max(index(res.end_time));
+        if last >= submitted.len()
+        {
+            submitted.resize(last + 1, 0);
+            committed.resize(last + 1, 0);
+            failed.resize(last + 1, 0);
+            latencies.resize(last + 1, vec![]);
+        }
+        submitted[index(res.start_time)] += 1;
+        if res.if_sucess == 1
+        {
+            committed[index(res.end_time)] += 1;
+            latencies[index(res.end_time)].push(res.end_time - res.start_time);
+        }
+        else
+        {
+            failed[index(res.end_time)] += 1;
+        }
+    }
+    (0..submitted.len())
+        .map(|i| {
+            let summary = LatencySummary::from_samples(&latencies[i]);
+            TimeBucket {
+                start: i as f64 * bucket,
+                submitted: submitted[i],
+                committed: committed[i],
+                failed: failed[i],
+                mean_latency: summary.mean,
+                p99_latency: summary.p99,
+            }
+        })
+        .collect()
+}
+
+// A test window is not stationary when the committed TPS of its second half differs from
+// the first half by more than MAX_THROUGHPUT_DRIFT, or when the latency trend over the
+// window adds more than MAX_LATENCY_GROWTH of the mean latency (queue build-up)
+pub const MAX_THROUGHPUT_DRIFT: f64 = 0.1;
+pub const MAX_LATENCY_GROWTH: f64 = 0.5;
+// Fewer buckets than this in the test window are not enough to judge
+const MIN_STEADY_STATE_BUCKETS: usize = 4;
+
+// Whether the test window of a run was in steady state, and the evidence
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SteadyState
+{
+    pub stationary: bool,
+    // committed transactions per second in each half of the test window
+    pub first_half_tps: f64,
+    pub second_half_tps: f64,
+    // least-squares slope of the mean latency of the buckets, in seconds per second
+    pub latency_slope: Option<f64>,
+    // why the window is not stationary
+    pub reason: Option<String>,
+}
+
+// Judge the buckets lying entirely inside the test window [time1, time2]
+pub fn steady_state(series: &[TimeBucket], bucket: f64, time1: f64, time2: f64) -> SteadyState
+{
+    let window: Vec<&TimeBucket> = series.iter()
+        .filter(|b| b.start >= time1 && b.start + bucket <= time2)
+        .collect();
+    if window.len() < MIN_STEADY_STATE_BUCKETS
+    {
+        return SteadyState {
+            reason: Some(format!("only {} buckets in the test window", window.len())),
+            ..Default::default()
+        };
+    }
+    let half = window.len() / 2;
+    let tps = |buckets: &[&TimeBucket]| {
+        buckets.iter().map(|b| b.committed).sum::<usize>() as f64 / (buckets.len() as f64 * bucket)
+    };
+    let first_half_tps = tps(&window[..half]);
+    let second_half_tps = tps(&window[half..]);
+
+    // fit latency = a + slope * time over the buckets that committed something
+    let points: Vec<(f64, f64)> = window.iter()
+        .filter_map(|b| b.mean_latency.map(|latency| (b.start, latency)))
+        .collect();
+    let latency_slope = if points.len() >= 2 {
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        if sxx > 0.0 { Some(sxy / sxx) } else { None }
+    } else {
+        None
+    };
+
+    let mut reason = None;
+    let drift = if first_half_tps > 0.0 {
+        (second_half_tps - first_half_tps).abs() / first_half_tps
+    } else if second_half_tps > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+    if drift > MAX_THROUGHPUT_DRIFT
+    {
+        reason = Some(format!("committed TPS changed from {:.2} to {:.2} between the halves of the test window", first_half_tps, second_half_tps));
+    }
+    else if let Some(slope) = latency_slope
+    {
+        let mean_latency = points.iter().map(|p| p.1).sum::<f64>() / points.len() as f64;
+        let growth = slope * (time2 - time1);
+        if mean_latency > 0.0 && growth > MAX_LATENCY_GROWTH * mean_latency
+        {
+            reason = Some(format!("latency grew by {:.2} s over the test window (mean {:.2} s)", growth, mean_latency));
+        }
+    }
+    SteadyState {
+        stationary: reason.is_none(),
+        first_half_tps,
+        second_half_tps,
+        latency_slope,
+        reason,
+    }
+}
+
+// Print an optional statistic, "nan" meaning no data
+pub fn fmt_opt(value: Option<f64>) -> String
+{
+    match value {
+        Some(v) => v.to_string(),
+        None => "nan".to_string(),
+    }
+}
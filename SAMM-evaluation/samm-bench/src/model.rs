@@ -0,0 +1,462 @@
+use crate::workload::{SwapDirection, SwapSpec};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Off-chain model of the pools of ../samm (SAMM swaps) and ../omniswap (CPMM swaps), with the
+// integer semantics of implements.move and math.move: u64/u128 arithmetic truncating like Move,
+// and every assertion or arithmetic error of the Move code reported as the abort it would cause.
+
+// Constants of implements.move
+pub const FEE_MULTIPLIER: u64 = 30;
+pub const FEE_SCALE: u64 = 10000;
+pub const MAX_POOL_VALUE: u64 = u64::MAX / FEE_SCALE;
+pub const MINIMAL_LIQUIDITY: u64 = 1000;
+pub const MAX_FEE_RATIO: u64 = 100;
+pub const MIN_FEE_RATIO: u64 = 10;
+pub const RATIO_MULTIPLIER: u64 = 1000;
+
+// Abort codes of implements.move and math.move
+pub const ERR_ZERO_AMOUNT: u64 = 0;
+pub const ERR_RESERVES_EMPTY: u64 = 1;
+pub const ERR_POOL_FULL: u64 = 2;
+pub const ERR_INSUFFICIENT_COIN_X: u64 = 3;
+pub const ERR_INSUFFICIENT_COIN_Y: u64 = 4;
+pub const ERR_OVERLIMIT: u64 = 6;
+pub const ERR_COIN_OUT_NUM_LESS_THAN_EXPECTED_MINIMUM: u64 = 7;
+pub const ERR_LIQUID_NOT_ENOUGH: u64 = 8;
+pub const ERR_U64_OVERFLOW: u64 = 13;
+pub const ERR_INCORRECT_SWAP: u64 = 14;
+pub const ERR_INSUFFICIENT_LIQUIDITY_MINTED: u64 = 15;
+pub const MATH_ERR_DIVIDE_BY_ZERO: u64 = 500;
+pub const MATH_ERR_U64_OVERFLOW: u64 = 501;
+
+// Why the Move code would not complete
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModelError
+{
+    // an assertion of the module aborted with this code
+    Abort { module: &'static str, code: u64 },
+    // the VM stopped on an overflow, an underflow or a division by zero
+    Arithmetic,
+}
+
+impl fmt::Display for ModelError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self {
+            ModelError::Abort { module, code } => write!(f, "abort {}::{}", module, code),
+            ModelError::Arithmetic => write!(f, "arithmetic error"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+fn abort(module: &'static str, code: u64) -> ModelError
+{
+    ModelError::Abort { module, code }
+}
+
+fn check<T>(value: Option<T>) -> Result<T, ModelError>
+{
+    value.ok_or(ModelError::Arithmetic)
+}
+
+// math::mul_div: x * y / z in u128, aborting when the result does not fit in a u64
+pub fn mul_div(x: u64, y: u64, z: u64) -> Result<u64, ModelError>
+{
+    if z == 0
+    {
+        return Err(abort("math", MATH_ERR_DIVIDE_BY_ZERO));
+    }
+    let r = x as u128 * y as u128 / z as u128;
+    u64::try_from(r).map_err(|_| abort("math", MATH_ERR_U64_OVERFLOW))
+}
+
+// math::mul_div_u128: the product itself may overflow u128
+pub fn mul_div_u128(x: u128, y: u128, z: u128) -> Result<u64, ModelError>
+{
+    if z == 0
+    {
+        return Err(abort("math", MATH_ERR_DIVIDE_BY_ZERO));
+    }
+    let r = check(x.checked_mul(y))? / z;
+    u64::try_from(r).map_err(|_| abort("math", MATH_ERR_U64_OVERFLOW))
+}
+
+// math::sqrt: Babylonian method on a u128
+pub fn sqrt(y: u128) -> u64
+{
+    if y < 4
+    {
+        return if y == 0 { 0 } else { 1 };
+    }
+    let mut z = y;
+    let mut x = y / 2 + 1;
+    while x < z
+    {
+        z = x;
+        x = (y / x + x) / 2;
+    }
+    z as u64
+}
+
+// math::sqrt_64
+pub fn sqrt_64(y: u64) -> u64
+{
+    sqrt(y as u128)
+}
+
+// implements::get_fee_to_fundation: coin_in * 0.3% * 20%
+pub fn get_fee_to_fundation(coin_in: u64) -> Result<u64, ModelError>
+{
+    mul_div(coin_in, FEE_MULTIPLIER / 5, FEE_SCALE)
+}
+
+// implements::get_multiplier: fee ratio of an input amount
+pub fn get_multiplier(coin_in: u64, coin_in_reserve: u64, _coin_out_reserve: u64) -> Result<u64, ModelError>
+{
+    let root = sqrt_64(check(coin_in_reserve.checked_div(coin_in))?);
+    let fac1 = check(RATIO_MULTIPLIER.checked_div(root))?;
+    if fac1 > MAX_FEE_RATIO
+    {
+        return Ok(MIN_FEE_RATIO);
+    }
+    Ok(MAX_FEE_RATIO - fac1)
+}
+
+// The fee ratio (in 1/FEE_SCALE) of get_multiplier_out, before the price is applied:
+// MAX_FEE_RATIO - 1.5 * RATIO_MULTIPLIER * coin_out / coin_out_reserve, or MIN_FEE_RATIO past MAX_FEE_RATIO.
+// Note that the Move code only falls back to MIN_FEE_RATIO once that term exceeds MAX_FEE_RATIO,
+// so the ratio is below MIN_FEE_RATIO while the term is in (MAX_FEE_RATIO - MIN_FEE_RATIO, MAX_FEE_RATIO].
+pub fn fee_ratio_out(coin_out: u64, coin_out_reserve: u64) -> Result<u64, ModelError>
+{
+    let dr = mul_div_u128(RATIO_MULTIPLIER as u128, 3, 2)?;
+    let fac1 = mul_div_u128(dr as u128, coin_out as u128, coin_out_reserve as u128)?;
+    Ok(if fac1 > MAX_FEE_RATIO { MIN_FEE_RATIO } else { MAX_FEE_RATIO - fac1 })
+}
+
+// implements::get_multiplier_out: fee multiplier of an output amount, in units of the input token
+// (the fee ratio times the price reserve_in / reserve_out)
+pub fn get_multiplier_out(coin_out: u64, coin_in_reserve: u64, coin_out_reserve: u64) -> Result<u64, ModelError>
+{
+    let ratio = fee_ratio_out(coin_out, coin_out_reserve)?;
+    mul_div_u128(ratio as u128, coin_in_reserve as u128, coin_out_reserve as u128)
+}
+
+// implements::get_amount_in_samm: input paying coin_out, the constant-product part plus the fee
+pub fn get_amount_in_samm(coin_out: u64, reserve_in: u64, reserve_out: u64, fee_multiplier: u64) -> Result<u64, ModelError>
+{
+    let tf = mul_div(coin_out, fee_multiplier, FEE_SCALE)?;
+    let new_reserve_out = check(reserve_out.checked_sub(coin_out))?;
+    let ef = mul_div_u128(coin_out as u128, reserve_in as u128, new_reserve_out as u128)?;
+    check(ef.checked_add(tf))
+}
+
+// implements::get_amount_out_samm: output of coin_in with a fee of fee_multiplier_raw / FEE_SCALE
+pub fn get_amount_out_samm(coin_in: u64, reserve_in: u64, reserve_out: u64, fee_multiplier_raw: u64) -> Result<u64, ModelError>
+{
+    let fee_multiplier = check(FEE_SCALE.checked_sub(fee_multiplier_raw))?;
+    let coin_in_val_after_fees = coin_in as u128 * fee_multiplier as u128;
+    let new_reserve_in = check((reserve_in as u128 * FEE_SCALE as u128).checked_add(coin_in_val_after_fees))?;
+    mul_div_u128(coin_in_val_after_fees, reserve_out as u128, new_reserve_in)
+}
+
+// implements::get_amount_out: constant-product output of coin_in minus the 0.3% fee
+pub fn get_amount_out(coin_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64, ModelError>
+{
+    get_amount_out_samm(coin_in, reserve_in, reserve_out, FEE_MULTIPLIER)
+}
+
+// implements::calc_optimal_coin_values
+pub fn calc_optimal_coin_values(coin_x_desired: u64, coin_y_desired: u64, coin_x_min: u64, coin_y_min: u64,
+    coin_x_reserve: u64, coin_y_reserve: u64) -> Result<(u64, u64), ModelError>
+{
+    if coin_x_reserve == 0 && coin_y_reserve == 0
+    {
+        return Ok((coin_x_desired, coin_y_desired));
+    }
+    let coin_y_returned = mul_div(coin_x_desired, coin_y_reserve, coin_x_reserve)?;
+    if coin_y_returned <= coin_y_desired
+    {
+        if coin_y_returned < coin_y_min
+        {
+            return Err(abort("implements", ERR_INSUFFICIENT_COIN_Y));
+        }
+        return Ok((coin_x_desired, coin_y_returned));
+    }
+    let coin_x_returned = mul_div(coin_y_desired, coin_x_reserve, coin_y_reserve)?;
+    if coin_x_returned > coin_x_desired
+    {
+        return Err(abort("implements", ERR_OVERLIMIT));
+    }
+    if coin_x_returned < coin_x_min
+    {
+        return Err(abort("implements", ERR_INSUFFICIENT_COIN_X));
+    }
+    Ok((coin_x_returned, coin_y_desired))
+}
+
+// The pricing of the swaps of a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Pricing
+{
+    // ../samm and ../samm-boost: the swap argument is the exact output, the input pays the SAMM fee
+    Samm,
+    // ../omniswap: the whole input coin is sold for at least the swap argument, 20% of the 0.3% fee is set aside
+    Cpmm,
+}
+
+impl Pricing
+{
+    // The pricing of a backend, by its name
+    pub fn of_backend(name: &str) -> Self
+    {
+        if name == "omniswap" { Pricing::Cpmm } else { Pricing::Samm }
+    }
+}
+
+// What a swap did to its pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapOutcome
+{
+    // taken from the input coin (the rest is returned to the sender)
+    pub amount_in: u64,
+    pub amount_out: u64,
+    // the part of amount_in paid as fee: the SAMM fee term, or the 0.3% of the CPMM
+    pub fee: u64,
+    // the part of amount_in set aside in the fee balance of the pool instead of the reserve
+    pub fee_to_fundation: u64,
+}
+
+// The balances of a Pool<X, Y>. In the benchmark pools X is USDT and Y is XBTC.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolModel
+{
+    pub coin_x: u64,
+    pub coin_y: u64,
+    pub fee_coin_x: u64,
+    pub fee_coin_y: u64,
+    pub lp_supply: u64,
+}
+
+impl PoolModel
+{
+    // A pool after its initial liquidity
+    pub fn new(coin_x: u64, coin_y: u64) -> Result<Self, ModelError>
+    {
+        let mut pool = Self::default();
+        pool.add_liquidity(coin_x, 1, coin_y, 1)?;
+        Ok(pool)
+    }
+
+    // The product of the reserves, which every swap must increase
+    pub fn k(&self) -> u128
+    {
+        self.coin_x as u128 * self.coin_y as u128
+    }
+
+    // (input reserve, output reserve) of a swap selling X when x_in
+    fn reserves(&self, x_in: bool) -> (u64, u64)
+    {
+        if x_in { (self.coin_x, self.coin_y) } else { (self.coin_y, self.coin_x) }
+    }
+
+    fn apply(&mut self, x_in: bool, to_reserve_in: u64, fee_to_fundation: u64, amount_out: u64) -> Result<(), ModelError>
+    {
+        let (old_x, old_y) = (self.coin_x, self.coin_y);
+        let (reserve_in, fee_in, reserve_out) = if x_in {
+            (&mut self.coin_x, &mut self.fee_coin_x, &mut self.coin_y)
+        } else {
+            (&mut self.coin_y, &mut self.fee_coin_y, &mut self.coin_x)
+        };
+        *reserve_in = check(reserve_in.checked_add(to_reserve_in))?;
+        *fee_in = check(fee_in.checked_add(fee_to_fundation))?;
+        // balance::split of the output, never short after the checks of the pricing
+        *reserve_out = check(reserve_out.checked_sub(amount_out))?;
+        // implements::assert_lp_value_is_increased
+        if old_x as u128 * old_y as u128 >= self.k()
+        {
+            return Err(abort("implements", ERR_INCORRECT_SWAP));
+        }
+        Ok(())
+    }
+
+    // implements::swap_out of ../samm: buy exactly coin_out, paying from a coin of coin_in_max
+    pub fn swap_samm(&mut self, x_in: bool, coin_in_max: u64, coin_out: u64) -> Result<SwapOutcome, ModelError>
+    {
+        if coin_in_max == 0
+        {
+            return Err(abort("implements", ERR_ZERO_AMOUNT));
+        }
+        let (reserve_in, reserve_out) = self.reserves(x_in);
+        if reserve_in == 0 || reserve_out == 0
+        {
+            return Err(abort("implements", ERR_RESERVES_EMPTY));
+        }
+        let fee_multiplier = get_multiplier_out(coin_out, reserve_in, reserve_out)?;
+        let amount_in = get_amount_in_samm(coin_out, reserve_in, reserve_out, fee_multiplier)?;
+        let fee = mul_div(coin_out, fee_multiplier, FEE_SCALE)?;
+        if coin_in_max < amount_in
+        {
+            return Err(abort("implements", ERR_COIN_OUT_NUM_LESS_THAN_EXPECTED_MINIMUM));
+        }
+        let mut next = *self;
+        next.apply(x_in, amount_in, 0, coin_out)?;
+        *self = next;
+        Ok(SwapOutcome {
+            amount_in,
+            amount_out: coin_out,
+            fee,
+            fee_to_fundation: 0,
+        })
+    }
+
+    // implements::swap_out of ../omniswap: sell the whole coin_in for at least coin_out_min
+    pub fn swap_cpmm(&mut self, x_in: bool, coin_in: u64, coin_out_min: u64) -> Result<SwapOutcome, ModelError>
+    {
+        if coin_in == 0
+        {
+            return Err(abort("implements", ERR_ZERO_AMOUNT));
+        }
+        let (reserve_in, reserve_out) = self.reserves(x_in);
+        if reserve_in == 0 || reserve_out == 0
+        {
+            return Err(abort("implements", ERR_RESERVES_EMPTY));
+        }
+        let fee_to_fundation = get_fee_to_fundation(coin_in)?;
+        let amount_out = get_amount_out(coin_in, reserve_in, reserve_out)?;
+        let fee = mul_div(coin_in, FEE_MULTIPLIER, FEE_SCALE)?;
+        if amount_out < coin_out_min
+        {
+            return Err(abort("implements", ERR_COIN_OUT_NUM_LESS_THAN_EXPECTED_MINIMUM));
+        }
+        let mut next = *self;
+        next.apply(x_in, coin_in - fee_to_fundation, fee_to_fundation, amount_out)?;
+        *self = next;
+        Ok(SwapOutcome {
+            amount_in: coin_in,
+            amount_out,
+            fee,
+            fee_to_fundation,
+        })
+    }
+
+    // A swap of the benchmark paying with a coin of coin_value (XBTC is Y, USDT is X)
+    pub fn swap(&mut self, pricing: Pricing, swap: &SwapSpec, coin_value: u64) -> Result<SwapOutcome, ModelError>
+    {
+        let x_in = swap.direction == SwapDirection::UsdtToXbtc;
+        match pricing {
+            Pricing::Samm => self.swap_samm(x_in, coin_value, swap.amount),
+            Pricing::Cpmm => self.swap_cpmm(x_in, coin_value, swap.amount),
+        }
+    }
+
+    // implements::add_liquidity: returns (coin_x taken, coin_y taken, LP minted)
+    pub fn add_liquidity(&mut self, coin_x: u64, coin_x_min: u64, coin_y: u64, coin_y_min: u64) -> Result<(u64, u64, u64), ModelError>
+    {
+        if coin_x == 0 || coin_y == 0
+        {
+            return Err(abort("implements", ERR_ZERO_AMOUNT));
+        }
+        let (optimal_x, optimal_y) = calc_optimal_coin_values(coin_x, coin_y, coin_x_min, coin_y_min, self.coin_x, self.coin_y)?;
+        let mut next = *self;
+        let provided = if self.lp_supply == 0 {
+            let initial = sqrt(optimal_x as u128 * optimal_y as u128);
+            if initial <= MINIMAL_LIQUIDITY
+            {
+                return Err(abort("implements", ERR_LIQUID_NOT_ENOUGH));
+            }
+            // the minimal liquidity is locked in the pool
+            next.lp_supply = MINIMAL_LIQUIDITY;
+            initial - MINIMAL_LIQUIDITY
+        } else {
+            let x_liq = check((self.lp_supply as u128 * optimal_x as u128).checked_div(self.coin_x as u128))?;
+            let y_liq = check((self.lp_supply as u128 * optimal_y as u128).checked_div(self.coin_y as u128))?;
+            let liq = x_liq.min(y_liq);
+            if liq >= u64::MAX as u128
+            {
+                return Err(abort("implements", ERR_U64_OVERFLOW));
+            }
+            liq as u64
+        };
+        if provided == 0
+        {
+            return Err(abort("implements", ERR_INSUFFICIENT_LIQUIDITY_MINTED));
+        }
+        next.coin_x = check(next.coin_x.checked_add(optimal_x))?;
+        next.coin_y = check(next.coin_y.checked_add(optimal_y))?;
+        if next.coin_x >= MAX_POOL_VALUE || next.coin_y >= MAX_POOL_VALUE
+        {
+            return Err(abort("implements", ERR_POOL_FULL));
+        }
+        next.lp_supply = check(next.lp_supply.checked_add(provided))?;
+        *self = next;
+        Ok((optimal_x, optimal_y, provided))
+    }
+
+    // implements::remove_liquidity: returns (coin_x out, coin_y out)
+    pub fn remove_liquidity(&mut self, lp: u64) -> Result<(u64, u64), ModelError>
+    {
+        if lp == 0
+        {
+            return Err(abort("implements", ERR_ZERO_AMOUNT));
+        }
+        let coin_x_out = mul_div(self.coin_x, lp, self.lp_supply)?;
+        let coin_y_out = mul_div(self.coin_y, lp, self.lp_supply)?;
+        let mut next = *self;
+        next.lp_supply = check(next.lp_supply.checked_sub(lp))?;
+        next.coin_x = check(next.coin_x.checked_sub(coin_x_out))?;
+        next.coin_y = check(next.coin_y.checked_sub(coin_y_out))?;
+        *self = next;
+        Ok((coin_x_out, coin_y_out))
+    }
+}
+
+// Totals of a simulated trading sequence
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SimulationSummary
+{
+    pub executed: usize,
+    pub aborted: usize,
+    // the number of swaps aborted with each error
+    pub errors: BTreeMap<String, usize>,
+    // input and fees paid in each token
+    pub amount_in_x: u128,
+    pub amount_in_y: u128,
+    pub fee_x: u128,
+    pub fee_y: u128,
+}
+
+// Run swaps one after the other on a pool, each paying with a coin of coin_value, like the pre-signed
+// transactions of the benchmark; an aborted swap leaves the pool unchanged
+pub fn simulate<'a>(pool: &mut PoolModel, pricing: Pricing, swaps: impl IntoIterator<Item = &'a SwapSpec>, coin_value: u64) -> SimulationSummary
+{
+    let mut summary = SimulationSummary::default();
+    for swap in swaps
+    {
+        match pool.swap(pricing, swap, coin_value) {
+            Ok(outcome) => {
+                summary.executed += 1;
+                if swap.direction == SwapDirection::UsdtToXbtc
+                {
+                    summary.amount_in_x += outcome.amount_in as u128;
+                    summary.fee_x += outcome.fee as u128;
+                }
+                else
+                {
+                    summary.amount_in_y += outcome.amount_in as u128;
+                    summary.fee_y += outcome.fee as u128;
+                }
+            }
+            Err(e) => {
+                summary.aborted += 1;
+                *summary.errors.entry(e.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+    summary
+}
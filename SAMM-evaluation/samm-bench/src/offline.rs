@@ -0,0 +1,224 @@
+use crate::backend::MoveCallSpec;
+use futures::future::join_all;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use sui_json_rpc_types::{SuiMoveNormalizedType, SuiObjectDataOptions};
+use sui_sdk::types::base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress};
+use sui_sdk::types::object::Owner;
+use sui_sdk::types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+use sui_sdk::types::transaction::{Argument, Command, ObjectArg, TransactionData};
+use sui_sdk::types::{Identifier, TypeTag};
+use sui_sdk::SuiClient;
+
+// The most objects read by one multi_get_objects request of the fullnode
+pub const MULTI_GET_LIMIT: usize = 50;
+
+// How an argument of a Move function is passed
+#[derive(Clone, Debug)]
+enum ParamKind
+{
+    // a value of this type
+    Pure(SuiMoveNormalizedType),
+    // an object, shared or owned, by value or by reference
+    Object { mutable: bool },
+    // a vector of owned objects by value
+    ObjectVector,
+    // the TxContext, added by the VM
+    TxContext,
+}
+
+fn param_kind(param: &SuiMoveNormalizedType) -> ParamKind
+{
+    match param {
+        SuiMoveNormalizedType::Reference(inner) | SuiMoveNormalizedType::MutableReference(inner) => {
+            if let SuiMoveNormalizedType::Struct { module, name, .. } = inner.as_ref()
+            {
+                if module == "tx_context" && name == "TxContext"
+                {
+                    return ParamKind::TxContext;
+                }
+            }
+            ParamKind::Object { mutable: matches!(param, SuiMoveNormalizedType::MutableReference(_)) }
+        }
+        SuiMoveNormalizedType::Struct { .. } => ParamKind::Object { mutable: true },
+        SuiMoveNormalizedType::Vector(inner) if matches!(inner.as_ref(), SuiMoveNormalizedType::Struct { .. }) => ParamKind::ObjectVector,
+        other => ParamKind::Pure(other.clone()),
+    }
+}
+
+fn json_object_id(value: &Value) -> Result<ObjectID, anyhow::Error>
+{
+    let id = value.as_str().ok_or_else(|| anyhow::anyhow!("Expected an object id, got {}", value))?;
+    Ok(ObjectID::from_str(id)?)
+}
+
+fn json_number<T: FromStr>(value: &Value) -> Result<T, anyhow::Error>
+{
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => return Err(anyhow::anyhow!("Expected a number, got {}", value)),
+    };
+    text.parse::<T>().map_err(|_| anyhow::anyhow!("Invalid number {}", text))
+}
+
+// Builds and signs transactions without asking the fullnode: the parameters of the Move functions
+// and the initial versions of the shared objects are resolved once, and the owned objects (coins
+// and gas) are read in batches before the transactions that use them are built
+pub struct OfflineBuilder
+{
+    pub gas_price: u64,
+    pub gas_budget: u64,
+    // the parameters of every function of the resolved packages, by (package, module, function)
+    functions: HashMap<(ObjectID, String, String), Vec<ParamKind>>,
+    // the initial version of each shared object
+    shared: HashMap<ObjectID, SequenceNumber>,
+    // the last reference read of each owned object
+    objects: HashMap<ObjectID, ObjectRef>,
+}
+
+impl OfflineBuilder
+{
+    pub async fn new(client: &SuiClient, gas_budget: u64) -> Result<Self, anyhow::Error>
+    {
+        Ok(Self {
+            gas_price: client.read_api().get_reference_gas_price().await?,
+            gas_budget,
+            functions: HashMap::new(),
+            shared: HashMap::new(),
+            objects: HashMap::new(),
+        })
+    }
+
+    // Read the signatures of every function of a package, once
+    pub async fn resolve_package(&mut self, client: &SuiClient, package: ObjectID) -> Result<(), anyhow::Error>
+    {
+        if self.functions.keys().any(|(resolved, _, _)| *resolved == package)
+        {
+            return Ok(());
+        }
+        let modules = client.read_api().get_normalized_move_modules_by_package(package).await?;
+        for (module_name, module) in modules
+        {
+            for (function_name, function) in module.exposed_functions
+            {
+                let params = function.parameters.iter().map(param_kind).collect();
+                self.functions.insert((package, module_name.clone(), function_name), params);
+            }
+        }
+        Ok(())
+    }
+
+    // Read the initial version of a shared object, once
+    pub async fn resolve_shared(&mut self, client: &SuiClient, object_id: ObjectID) -> Result<(), anyhow::Error>
+    {
+        if self.shared.contains_key(&object_id)
+        {
+            return Ok(());
+        }
+        let response = client.read_api().get_object_with_options(object_id, SuiObjectDataOptions::new().with_owner()).await?;
+        match response.data.and_then(|data| data.owner) {
+            Some(Owner::Shared { initial_shared_version }) => {
+                self.shared.insert(object_id, initial_shared_version);
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("Object {} is not shared", object_id)),
+        }
+    }
+
+    // Read the current reference of owned objects, MULTI_GET_LIMIT per request
+    pub async fn resolve_objects(&mut self, client: &SuiClient, object_ids: &[ObjectID]) -> Result<(), anyhow::Error>
+    {
+        let requests = object_ids.chunks(MULTI_GET_LIMIT).map(|chunk| {
+            client.read_api().multi_get_object_with_options(chunk.to_vec(), SuiObjectDataOptions::new())
+        });
+        for responses in join_all(requests).await
+        {
+            for response in responses?
+            {
+                let data = response.data.ok_or_else(|| anyhow::anyhow!("An object to pay with does not exist"))?;
+                self.objects.insert(data.object_id, data.object_ref());
+            }
+        }
+        Ok(())
+    }
+
+    fn object_arg(&self, object_id: ObjectID, mutable: bool) -> Result<ObjectArg, anyhow::Error>
+    {
+        if let Some(initial_shared_version) = self.shared.get(&object_id)
+        {
+            return Ok(ObjectArg::SharedObject { id: object_id, initial_shared_version: *initial_shared_version, mutable });
+        }
+        self.objects.get(&object_id)
+            .map(|object_ref| ObjectArg::ImmOrOwnedObject(*object_ref))
+            .ok_or_else(|| anyhow::anyhow!("Object {} was not resolved", object_id))
+    }
+
+    fn pure_arg(builder: &mut ProgrammableTransactionBuilder, ty: &SuiMoveNormalizedType, value: &Value) -> Result<Argument, anyhow::Error>
+    {
+        match ty {
+            SuiMoveNormalizedType::Bool => builder.pure(value.as_bool().ok_or_else(|| anyhow::anyhow!("Expected a bool, got {}", value))?),
+            SuiMoveNormalizedType::U8 => builder.pure(json_number::<u8>(value)?),
+            SuiMoveNormalizedType::U16 => builder.pure(json_number::<u16>(value)?),
+            SuiMoveNormalizedType::U32 => builder.pure(json_number::<u32>(value)?),
+            SuiMoveNormalizedType::U64 => builder.pure(json_number::<u64>(value)?),
+            SuiMoveNormalizedType::U128 => builder.pure(json_number::<u128>(value)?),
+            SuiMoveNormalizedType::Address => builder.pure(SuiAddress::from_str(value.as_str().unwrap_or_default())?),
+            other => Err(anyhow::anyhow!("Parameters of type {:?} cannot be built offline", other)),
+        }
+    }
+
+    // The transaction of a call, paid with the gas object, built from the resolved references
+    pub fn build(&self, sender: SuiAddress, call: &MoveCallSpec, gas_object: ObjectID) -> Result<TransactionData, anyhow::Error>
+    {
+        self.build_batch(sender, std::slice::from_ref(call), gas_object)
+    }
+
+    // One programmable transaction making the calls in order, paid with the gas object
+    pub fn build_batch(&self, sender: SuiAddress, calls: &[MoveCallSpec], gas_object: ObjectID) -> Result<TransactionData, anyhow::Error>
+    {
+        let mut builder = ProgrammableTransactionBuilder::new();
+        for call in calls
+        {
+            self.add_call(&mut builder, call)?;
+        }
+        let gas = *self.objects.get(&gas_object)
+            .ok_or_else(|| anyhow::anyhow!("Gas object {} was not resolved", gas_object))?;
+        Ok(TransactionData::new_programmable(sender, vec![gas], builder.finish(), self.gas_budget, self.gas_price))
+    }
+
+    fn add_call(&self, builder: &mut ProgrammableTransactionBuilder, call: &MoveCallSpec) -> Result<(), anyhow::Error>
+    {
+        let params = self.functions.get(&(call.package, call.module.to_string(), call.function.to_string()))
+            .ok_or_else(|| anyhow::anyhow!("Function {}::{}::{} was not resolved", call.package, call.module, call.function))?;
+        let params: Vec<&ParamKind> = params.iter().filter(|param| !matches!(param, ParamKind::TxContext)).collect();
+        if params.len() != call.call_args.len()
+        {
+            return Err(anyhow::anyhow!("{}::{} takes {} arguments, got {}", call.module, call.function, params.len(), call.call_args.len()));
+        }
+        let mut arguments = vec![];
+        for (param, arg) in params.into_iter().zip(call.call_args.iter())
+        {
+            let value = arg.to_json_value();
+            let argument = match param {
+                ParamKind::Pure(ty) => Self::pure_arg(builder, ty, &value)?,
+                ParamKind::Object { mutable } => builder.obj(self.object_arg(json_object_id(&value)?, *mutable)?)?,
+                ParamKind::ObjectVector => {
+                    let ids = value.as_array().ok_or_else(|| anyhow::anyhow!("Expected a vector of objects, got {}", value))?;
+                    let objects = ids.iter()
+                        .map(|id| self.object_arg(json_object_id(id)?, true))
+                        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+                    builder.make_obj_vec(objects)?
+                }
+                ParamKind::TxContext => continue,
+            };
+            arguments.push(argument);
+        }
+        let type_args = call.type_args.iter().cloned()
+            .map(|arg| arg.try_into())
+            .collect::<Result<Vec<TypeTag>, _>>()?;
+        builder.command(Command::move_call(call.package, Identifier::new(call.module)?, Identifier::new(call.function)?, type_args, arguments));
+        Ok(())
+    }
+}
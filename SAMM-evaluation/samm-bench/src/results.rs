@@ -0,0 +1,228 @@
+use crate::audit::AuditReport;
+use crate::config::ExperimentConfig;
+use crate::execution::{ExecutionReturn, ExperimentReturn};
+use crate::metrics::{fmt_opt, LatencyHistogram, ShardResult, TimeBucket};
+use crate::failure::{Failure, FailureClass};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+// Bump when a field of RunRecord or TxRecord changes meaning or is removed
+pub const RESULT_SCHEMA_VERSION: u32 = 2;
+
+// One JSON line per run, in the result folder
+pub const RUNS_FILE: &str = "runs.jsonl";
+// Per-transaction records of a run, in its raw folder
+pub const TX_RECORDS_FILE: &str = "transactions.csv";
+// Latency histogram of a run, in its raw folder
+pub const HISTOGRAM_FILE: &str = "latency_histogram.json";
+// One JSON line per group with its knee and the steps of the search
+pub const KNEES_FILE: &str = "knees.jsonl";
+// Time series of a run, in its raw folder
+pub const TIME_SERIES_FILE: &str = "timeseries.csv";
+// Per-shard metrics of a run, in its raw folder
+pub const SHARDS_FILE: &str = "shards.csv";
+// Pools before and after a run and their reconciliation with the successful swaps, in its raw folder
+pub const AUDIT_FILE: &str = "audit.json";
+// Prefix of the raw file of each trader client
+pub const CLIENT_FILE_PREFIX: &str = "Client ";
+
+// Everything needed to interpret one run without the rest of the folder
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RunRecord
+{
+    pub schema_version: u32,
+    pub amm: String,
+    pub config: ExperimentConfig,
+    pub group: usize,
+    pub num_shards: usize,
+    pub target_tps: usize,
+    pub repetition: usize,
+    pub seed: u64,
+    pub validator_version: String,
+    // local start time of the run
+    pub started_at: String,
+    // successful transactions per second in the test window
+    pub achieved_tps: f64,
+    pub metrics: ExperimentReturn,
+}
+
+impl RunRecord
+{
+    pub fn append_to(&self, folder: &Path) -> Result<(), anyhow::Error>
+    {
+        let mut file = OpenOptions::new().create(true).append(true).open(folder.join(RUNS_FILE))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+// Read back every run of a result folder
+pub fn read_run_records(folder: &Path) -> Result<Vec<RunRecord>, anyhow::Error>
+{
+    let content = std::fs::read_to_string(folder.join(RUNS_FILE))?;
+    let mut records = vec![];
+    for line in content.lines().filter(|line| !line.trim().is_empty())
+    {
+        records.push(serde_json::from_str(line)?);
+    }
+    Ok(records)
+}
+
+// Raw file of one client: "if_sucess, start_time, end_time, shard" per transaction,
+// followed by the failure class for failed ones
+pub fn write_client_file(folder: &Path, id: usize, results: &[ExecutionReturn]) -> Result<(), anyhow::Error>
+{
+    let mut raw_file = File::create(folder.join(format!("{}{}", CLIENT_FILE_PREFIX, id)))?;
+    for res in results
+    {
+        match &res.failure {
+            Some(failure) if res.if_sucess != 1 => writeln!(
+                &mut raw_file,
+                "{}, {}, {}, {}, {}",
+                res.if_sucess, res.start_time, res.end_time, res.shard, failure.class.label()
+            )?,
+            _ => writeln!(
+                &mut raw_file,
+                "{}, {}, {}, {}",
+                res.if_sucess, res.start_time, res.end_time, res.shard
+            )?,
+        }
+    }
+    Ok(())
+}
+
+// Read a raw client file back. Only the columns of the raw format are restored.
+pub fn read_client_file(path: &Path) -> Result<Vec<ExecutionReturn>, anyhow::Error>
+{
+    let content = std::fs::read_to_string(path)?;
+    let mut results = vec![];
+    for (number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty())
+    {
+        let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+        if fields.len() < 3
+        {
+            return Err(anyhow::anyhow!("{}:{}: expected 3 columns", path.display(), number + 1));
+        }
+        let if_sucess = fields[0].parse::<usize>()?;
+        // older files have neither the shard nor the failure class
+        let shard = fields.get(3).and_then(|shard| shard.parse().ok()).unwrap_or(0);
+        let failure = if if_sucess == 1 {
+            None
+        } else {
+            let class = fields.get(4)
+                .and_then(|label| FailureClass::from_label(label))
+                .unwrap_or(FailureClass::Rpc);
+            Some(Failure::new(class, String::new()))
+        };
+        results.push(ExecutionReturn::new(if_sucess, fields[1].parse()?, fields[2].parse()?, shard, String::new(), failure, ""));
+    }
+    Ok(results)
+}
+
+pub fn write_histogram(folder: &Path, histogram: &LatencyHistogram) -> Result<(), anyhow::Error>
+{
+    std::fs::write(folder.join(HISTOGRAM_FILE), serde_json::to_string(histogram)?)?;
+    Ok(())
+}
+
+pub fn write_audit(folder: &Path, report: &AuditReport) -> Result<(), anyhow::Error>
+{
+    std::fs::write(folder.join(AUDIT_FILE), serde_json::to_string_pretty(report)?)?;
+    Ok(())
+}
+
+// One row per shard: shard, submitted, success, fail, throughput, mean and p99 latency
+pub fn write_shard_results(folder: &Path, shards: &[ShardResult]) -> Result<(), anyhow::Error>
+{
+    let mut writer = csv::Writer::from_path(folder.join(SHARDS_FILE))?;
+    writer.write_record(["shard", "submitted", "success", "fail", "throughput", "mean_latency", "p99_latency"])?;
+    for shard in shards
+    {
+        writer.write_record([
+            shard.shard.to_string(),
+            shard.submitted.to_string(),
+            shard.success.to_string(),
+            shard.fail.to_string(),
+            shard.throughput.to_string(),
+            fmt_opt(shard.latency.mean),
+            fmt_opt(shard.latency.p99),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Per-second (or other bucket) series of a run
+pub fn write_time_series(path: &Path, series: &[TimeBucket]) -> Result<(), anyhow::Error>
+{
+    let mut writer = csv::Writer::from_path(path)?;
+    for bucket in series
+    {
+        writer.serialize(bucket)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// One row of transactions.csv
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TxRecord
+{
+    pub client_id: usize,
+    // the account that signed the transaction, empty when unknown
+    pub sender: String,
+    pub shard: usize,
+    // the interface function called (swap, add_liquidity...)
+    pub operation: String,
+    pub digest: String,
+    pub success: bool,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub latency: f64,
+    // whether the transaction was sent inside the test window
+    pub in_test_window: bool,
+    pub failure_class: Option<String>,
+    pub failure_message: Option<String>,
+}
+
+pub struct TxRecordWriter
+{
+    writer: csv::Writer<File>,
+}
+
+impl TxRecordWriter
+{
+    pub fn create(path: &Path) -> Result<Self, anyhow::Error>
+    {
+        Ok(Self {
+            writer: csv::Writer::from_path(path)?,
+        })
+    }
+
+    pub fn write(&mut self, client_id: usize, res: &ExecutionReturn, time1: f64, time2: f64) -> Result<(), anyhow::Error>
+    {
+        self.writer.serialize(TxRecord {
+            client_id,
+            sender: res.sender.map(|sender| sender.to_string()).unwrap_or_default(),
+            shard: res.shard,
+            operation: res.operation.to_string(),
+            digest: res.digest.clone(),
+            success: res.if_sucess == 1,
+            start_time: res.start_time,
+            end_time: res.end_time,
+            latency: res.end_time - res.start_time,
+            in_test_window: res.start_time > time1 && res.start_time < time2,
+            failure_class: res.failure.as_ref().map(|failure| failure.class.label()),
+            failure_message: res.failure.as_ref().map(|failure| failure.message.clone()),
+        })?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), anyhow::Error>
+    {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
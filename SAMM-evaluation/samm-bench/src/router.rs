@@ -0,0 +1,311 @@
+use crate::build_contract::COIN_EACH_OBJ;
+use crate::build_tx::DataAndSender;
+use crate::model::{PoolModel, Pricing};
+use crate::offline::MULTI_GET_LIMIT;
+use crate::workload::{SwapDirection, SwapSpec};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use sui_json_rpc_types::{SuiObjectDataOptions, SuiObjectResponse, SuiParsedData};
+use sui_sdk::types::base_types::ObjectID;
+use sui_sdk::SuiClient;
+use tokio::time::sleep;
+
+// How a trader client picks the shard of a swap when it sends it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingPolicy
+{
+    // the shard chosen when the workload was planned (no routing)
+    #[default]
+    Fixed,
+    // a shard drawn uniformly
+    Random,
+    // the shard with the smallest reserve of the output token
+    SmallestPool,
+    // the shard with the best price for the swap, quoted by the off-chain model of the pools
+    BestQuote,
+    // the shards in turn, shared by all clients
+    RoundRobin,
+}
+
+// Routing of the swaps, declared in the experiment file as the [router] table
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RouterConfig
+{
+    pub policy: RoutingPolicy,
+    // seconds between two reads of the reserves of every pool
+    pub refresh_interval: f64,
+}
+
+impl Default for RouterConfig
+{
+    fn default() -> Self
+    {
+        Self {
+            policy: RoutingPolicy::Fixed,
+            refresh_interval: 0.5,
+        }
+    }
+}
+
+// A routed swap: one candidate per shard, the router picks the one to send. Only the candidate of the
+// planned shard is signed before the run, the others when they are picked
+#[derive(Clone)]
+pub struct Route
+{
+    pub swap: SwapSpec,
+    // candidates[shard] pays with a coin of that shard
+    pub candidates: Vec<DataAndSender>,
+}
+
+// Reserves of the USDT-XBTC pool of one shard (the pool is Pool<USDT, XBTC>)
+#[derive(Clone, Copy, Debug)]
+pub struct PoolReserves
+{
+    pub usdt: u64,
+    pub xbtc: u64,
+}
+
+impl PoolReserves
+{
+    // (input reserve, output reserve) of a swap
+    pub fn oriented(&self, direction: SwapDirection) -> (u64, u64)
+    {
+        match direction {
+            SwapDirection::XbtcToUsdt => (self.xbtc, self.usdt),
+            SwapDirection::UsdtToXbtc => (self.usdt, self.xbtc),
+        }
+    }
+
+    // Input paid per unit of output by the swap, paying with a pre-minted coin; None if it would abort
+    pub fn quote(&self, pricing: Pricing, swap: &SwapSpec) -> Option<f64>
+    {
+        let mut pool = PoolModel { coin_x: self.usdt, coin_y: self.xbtc, ..Default::default() };
+        let outcome = pool.swap(pricing, swap, COIN_EACH_OBJ as u64).ok()?;
+        Some(outcome.amount_in as f64 / outcome.amount_out.max(1) as f64)
+    }
+}
+
+// `name` in a Move struct rendered as JSON, with or without its "fields" wrapper
+fn json_field<'a>(value: &'a Value, name: &str) -> Option<&'a Value>
+{
+    value.get(name).or_else(|| value.get("fields").and_then(|fields| fields.get(name)))
+}
+
+// A u64 rendered as a string or a number, or a Balance rendered as {"value": ...}
+fn json_u64(value: &Value) -> Option<u64>
+{
+    match value {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_u64(),
+        Value::Object(_) => json_field(value, "value").and_then(json_u64),
+        _ => None,
+    }
+}
+
+// The Move content of an object read with its content, as JSON
+fn content_json(object_id: ObjectID, response: SuiObjectResponse) -> Result<Value, anyhow::Error>
+{
+    match response.data.and_then(|data| data.content) {
+        Some(SuiParsedData::MoveObject(object)) => Ok(object.fields.to_json_value()),
+        _ => Err(anyhow::anyhow!("Object {} has no Move content", object_id)),
+    }
+}
+
+async fn object_json(client: &SuiClient, object_id: ObjectID) -> Result<Value, anyhow::Error>
+{
+    let response = client.read_api()
+        .get_object_with_options(object_id, SuiObjectDataOptions::new().with_content())
+        .await?;
+    content_json(object_id, response)
+}
+
+// The object holding the pool of a Global: the pool is the only entry of its `pools` bag, whose
+// field object keeps its id while the balances change
+pub async fn pool_object(client: &SuiClient, global: ObjectID) -> Result<ObjectID, anyhow::Error>
+{
+    let global_json = object_json(client, global).await?;
+    let bag_id = json_field(&global_json, "pools")
+        .and_then(|pools| json_field(pools, "id"))
+        .and_then(|id| json_field(id, "id").or(Some(id)))
+        .and_then(|id| id.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Global {} has no pools bag", global))?
+        .parse::<ObjectID>()?;
+    let fields = client.read_api().get_dynamic_fields(bag_id, None, None).await?;
+    let pool_field = fields.data.first()
+        .ok_or_else(|| anyhow::anyhow!("Global {} has no pool", global))?;
+    Ok(pool_field.object_id)
+}
+
+// The balances of the pool of a Global, from the JSON of its field object
+fn pool_from_json(global: ObjectID, field_json: &Value) -> Result<PoolModel, anyhow::Error>
+{
+    let pool = json_field(field_json, "value")
+        .ok_or_else(|| anyhow::anyhow!("Pool of {} has no value", global))?;
+    let balance = |name: &str| json_field(pool, name).and_then(json_u64)
+        .ok_or_else(|| anyhow::anyhow!("Pool of {} has no {}", global, name));
+    Ok(PoolModel {
+        coin_x: balance("coin_x")?,
+        coin_y: balance("coin_y")?,
+        fee_coin_x: balance("fee_coin_x")?,
+        fee_coin_y: balance("fee_coin_y")?,
+        lp_supply: balance("lp_supply")?,
+    })
+}
+
+// Read the balances of the pool of a Global
+pub async fn read_pool(client: &SuiClient, global: ObjectID) -> Result<PoolModel, anyhow::Error>
+{
+    let pool_object = pool_object(client, global).await?;
+    let field_json = object_json(client, pool_object).await?;
+    pool_from_json(global, &field_json)
+}
+
+impl From<PoolModel> for PoolReserves
+{
+    // Pool<USDT, XBTC>: coin_x is USDT, coin_y is XBTC
+    fn from(pool: PoolModel) -> Self
+    {
+        Self {
+            usdt: pool.coin_x,
+            xbtc: pool.coin_y,
+        }
+    }
+}
+
+// Picks the shard of each routed swap from the last reserves read
+pub struct Router
+{
+    pub policy: RoutingPolicy,
+    pub pricing: Pricing,
+    // the Global of each shard
+    pub globals: Vec<ObjectID>,
+    pub refresh_interval: f64,
+    reserves: RwLock<Vec<Option<PoolReserves>>>,
+    // the object holding the pool of each shard, found on the first refresh
+    pool_objects: RwLock<Option<Vec<ObjectID>>>,
+    // the requests sent to the fullnode to read the reserves
+    rpcs: AtomicUsize,
+    next: AtomicUsize,
+}
+
+impl Router
+{
+    pub fn new(config: &RouterConfig, pricing: Pricing, globals: Vec<ObjectID>) -> Self
+    {
+        let num_shards = globals.len();
+        Self {
+            policy: config.policy,
+            pricing,
+            globals,
+            refresh_interval: config.refresh_interval,
+            reserves: RwLock::new(vec![None; num_shards]),
+            pool_objects: RwLock::new(None),
+            rpcs: AtomicUsize::new(0),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    // Read the reserves of every shard, MULTI_GET_LIMIT pools per request once their objects are known;
+    // a shard that cannot be read keeps its last reserves
+    pub async fn refresh(&self, client: &SuiClient) -> Result<(), anyhow::Error>
+    {
+        let pool_objects = match self.pool_objects.read().unwrap().clone() {
+            Some(pool_objects) => pool_objects,
+            None => self.find_pool_objects(client).await?,
+        };
+        let mut last_error = None;
+        for (chunk, objects) in pool_objects.chunks(MULTI_GET_LIMIT).enumerate()
+        {
+            self.rpcs.fetch_add(1, Ordering::Relaxed);
+            let responses = match client.read_api().multi_get_object_with_options(objects.to_vec(), SuiObjectDataOptions::new().with_content()).await {
+                Ok(responses) => responses,
+                Err(e) => {
+                    last_error = Some(anyhow::Error::from(e));
+                    continue;
+                }
+            };
+            for (offset, (object_id, response)) in objects.iter().zip(responses).enumerate()
+            {
+                let shard = chunk * MULTI_GET_LIMIT + offset;
+                match content_json(*object_id, response).and_then(|json| pool_from_json(self.globals[shard], &json)) {
+                    Ok(pool) => self.reserves.write().unwrap()[shard] = Some(pool.into()),
+                    Err(e) => last_error = Some(e),
+                }
+            }
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    // The object holding the pool of every shard, two requests per shard
+    async fn find_pool_objects(&self, client: &SuiClient) -> Result<Vec<ObjectID>, anyhow::Error>
+    {
+        let mut pool_objects = vec![];
+        for global in &self.globals
+        {
+            self.rpcs.fetch_add(2, Ordering::Relaxed);
+            pool_objects.push(pool_object(client, *global).await?);
+        }
+        *self.pool_objects.write().unwrap() = Some(pool_objects.clone());
+        Ok(pool_objects)
+    }
+
+    // The requests sent to read the reserves so far
+    pub fn rpcs(&self) -> usize
+    {
+        self.rpcs.load(Ordering::Relaxed)
+    }
+
+    // Refresh the reserves every refresh_interval seconds until `until` seconds after origin_time
+    pub fn spawn_refresher(self: Arc<Self>, client: SuiClient, origin_time: SystemTime, until: f64)
+        -> tokio::task::JoinHandle<()>
+    {
+        tokio::spawn(async move {
+            while SystemTime::now().duration_since(origin_time).map_or(0.0, |elapsed| elapsed.as_secs_f64()) < until
+            {
+                if let Err(e) = self.refresh(&client).await
+                {
+                    eprintln!("Failed to read the pool reserves: {}", e);
+                }
+                sleep(Duration::from_secs_f64(self.refresh_interval)).await;
+            }
+        })
+    }
+
+    // The shard to send the swap to, None to keep the planned one
+    pub fn choose<R: Rng>(&self, swap: &SwapSpec, rng: &mut R) -> Option<usize>
+    {
+        let num_shards = self.globals.len();
+        if num_shards == 0
+        {
+            return None;
+        }
+        match self.policy {
+            RoutingPolicy::Fixed => None,
+            RoutingPolicy::Random => Some(rng.gen_range(0..num_shards)),
+            RoutingPolicy::RoundRobin => Some(self.next.fetch_add(1, Ordering::Relaxed) % num_shards),
+            RoutingPolicy::SmallestPool => {
+                let reserves = self.reserves.read().unwrap();
+                (0..num_shards)
+                    .filter_map(|shard| reserves[shard].map(|pool| (pool.oriented(swap.direction).1, shard)))
+                    .min()
+                    .map(|(_, shard)| shard)
+            }
+            RoutingPolicy::BestQuote => {
+                let reserves = self.reserves.read().unwrap();
+                (0..num_shards)
+                    .filter_map(|shard| reserves[shard].and_then(|pool| pool.quote(self.pricing, swap)).map(|price| (price, shard)))
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, shard)| shard)
+            }
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use crate::config::ExperimentConfig;
+use crate::execution::ExperimentReturn;
+use crate::metrics::LatencyHistogram;
+use crate::results::KNEES_FILE;
+use crate::stopping::{evaluate, FiredRule, RaiseMinTps, StepObservation, StopRule};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+// How the expected TPS of the steps of a group are chosen
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStrategy
+{
+    // from min_tps up by tps_interval until the group fails (the original sweep)
+    #[default]
+    Linear,
+    // double the TPS until the SLO is violated, then bisect down to tps_interval
+    Bisection,
+}
+
+// The service level objective a step must meet to count below the knee
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Slo
+{
+    // latency percentile checked, e.g. 0.99
+    pub percentile: f64,
+    // maximal latency at that percentile (seconds)
+    pub max_latency: f64,
+    // minimal successful transactions over the expected ones
+    pub min_success_ratio: f64,
+}
+
+impl Default for Slo
+{
+    fn default() -> Self
+    {
+        Self {
+            percentile: 0.99,
+            max_latency: 5.0,
+            min_success_ratio: 0.8,
+        }
+    }
+}
+
+// The repetitions of one expected TPS, reduced to what the search needs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StepSummary
+{
+    pub tps: usize,
+    pub repetitions: usize,
+    pub success: usize,
+    pub fail: usize,
+    // mean of the average latency of the repetitions, None if one had no successful transaction
+    pub mean_latency: Option<f64>,
+    // latency at the SLO percentile over all repetitions
+    pub percentile_latency: Option<f64>,
+    // successful transactions over the expected ones (tps * time_test per repetition)
+    pub success_ratio: f64,
+    pub meets_slo: bool,
+    // the first stopping rule that fired on this step
+    #[serde(default)]
+    pub stopped_by: Option<FiredRule>,
+}
+
+impl StepSummary
+{
+    pub fn new(tps: usize, results: &[ExperimentReturn], time_test: f64, slo: &Slo, rules: &[StopRule]) -> Self
+    {
+        let success: usize = results.iter().map(|result| result.success).sum();
+        let fail: usize = results.iter().map(|result| result.fail).sum();
+        let averages: Option<Vec<f64>> = results.iter().map(|result| result.average_latency()).collect();
+        let mean_latency = averages
+            .filter(|averages| !averages.is_empty())
+            .map(|averages| averages.iter().sum::<f64>() / averages.len() as f64);
+        let mut histogram = LatencyHistogram::new();
+        for result in results
+        {
+            histogram.merge(&result.histogram);
+        }
+        let percentile_latency = histogram.value_at_quantile(slo.percentile);
+        let exp_success = tps as f64 * time_test;
+        let success_ratio = if exp_success > 0.0 && !results.is_empty() {
+            (success as f64 / exp_success) / results.len() as f64
+        } else {
+            0.0
+        };
+        let meets_slo = percentile_latency.map_or(false, |latency| latency <= slo.max_latency)
+            && success_ratio >= slo.min_success_ratio;
+        let stopped_by = evaluate(rules, &StepObservation {
+            success,
+            fail,
+            mean_latency,
+            success_ratio,
+            histogram: &histogram,
+            validator_crashed: results.iter().any(|result| result.validator_crashed),
+        });
+        Self {
+            tps,
+            repetitions: results.len(),
+            success,
+            fail,
+            mean_latency,
+            percentile_latency,
+            success_ratio,
+            meets_slo,
+            stopped_by,
+        }
+    }
+
+    // Below the knee: the SLO holds and no stopping rule fired
+    pub fn passes(&self) -> bool
+    {
+        self.meets_slo && self.stopped_by.is_none()
+    }
+}
+
+// A way to walk the expected TPS of a group. The harness asks for the next TPS,
+// runs its repetitions and reports them back, until next_tps() returns None.
+pub trait TpsSearch
+{
+    fn next_tps(&self) -> Option<usize>;
+    fn observe(&mut self, step: &StepSummary);
+    // why the search ended early, if it did
+    fn stop_reason(&self) -> Option<&str>;
+}
+
+// The original sweep: stops when a stopping rule fires, and raises the min_tps of
+// the next groups while the latency stays low
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LinearSweep
+{
+    pub current: usize,
+    pub max_tps: usize,
+    pub interval: usize,
+    pub raise: RaiseMinTps,
+    // whether the latency of this group stayed below raise.max_mean_latency so far
+    pub flag2s: bool,
+    // the min_tps for the next groups, when raised
+    pub raised_min_tps: Option<usize>,
+    pub stop_reason: Option<String>,
+}
+
+impl TpsSearch for LinearSweep
+{
+    fn next_tps(&self) -> Option<usize>
+    {
+        if self.stop_reason.is_some() || self.current > self.max_tps
+        {
+            return None;
+        }
+        Some(self.current)
+    }
+
+    fn observe(&mut self, step: &StepSummary)
+    {
+        // If there are too many failures, the latency is too large or the number of successful transactions is too smaller than expected, we stop the test of this group
+        if let Some(fired) = &step.stopped_by
+        {
+            self.stop_reason = Some(fired.message.clone());
+            return;
+        }
+        // to speedup
+        // If the latency is small and the failure rate is small, we can increase the minimum tps for the next group
+        let latency = step.mean_latency.unwrap_or(f64::INFINITY);
+        if latency < self.raise.max_mean_latency && step.success_ratio > self.raise.min_success_ratio && self.flag2s
+        {
+            self.raised_min_tps = Some(step.tps);
+        }
+        if latency >= self.raise.max_mean_latency
+        {
+            self.flag2s = false;
+        }
+        self.current += self.interval;
+    }
+
+    fn stop_reason(&self) -> Option<&str>
+    {
+        self.stop_reason.as_deref()
+    }
+}
+
+// Exponential probe then bisection on the SLO: the TPS doubles from the start while the SLO holds,
+// then the bracket [last passing, first failing] is halved until it is narrower than the resolution.
+// A stopping rule makes a step fail like a violated SLO, except a validator crash, which ends the search
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BisectionSearch
+{
+    // the highest TPS meeting the SLO so far
+    pub passed: Option<usize>,
+    // the lowest TPS violating the SLO so far
+    pub failed: Option<usize>,
+    pub next: Option<usize>,
+    pub max_tps: usize,
+    pub resolution: usize,
+    // the rule that fired on the step at `failed`, or the crash that ended the search
+    #[serde(default)]
+    pub stopped_by: Option<FiredRule>,
+}
+
+impl BisectionSearch
+{
+    pub fn new(start_tps: usize, max_tps: usize, resolution: usize) -> Self
+    {
+        Self {
+            passed: None,
+            failed: None,
+            next: Some(start_tps.max(1).min(max_tps)),
+            max_tps,
+            resolution: resolution.max(1),
+            stopped_by: None,
+        }
+    }
+}
+
+impl TpsSearch for BisectionSearch
+{
+    fn next_tps(&self) -> Option<usize>
+    {
+        self.next
+    }
+
+    fn observe(&mut self, step: &StepSummary)
+    {
+        if let Some(fired) = step.stopped_by.as_ref().filter(|fired| fired.rule == StopRule::ValidatorCrash)
+        {
+            // the step says nothing about the load the pools can take
+            self.stopped_by = Some(fired.clone());
+            self.next = None;
+            return;
+        }
+        if step.passes()
+        {
+            self.passed = Some(self.passed.map_or(step.tps, |passed| passed.max(step.tps)));
+        }
+        else if self.failed.map_or(true, |failed| step.tps < failed)
+        {
+            self.failed = Some(step.tps);
+            self.stopped_by = step.stopped_by.clone();
+        }
+        self.next = match self.failed {
+            // still probing: double, capped by max_tps
+            None if step.tps >= self.max_tps => None,
+            None => Some((step.tps * 2).min(self.max_tps)),
+            Some(failed) => {
+                // a noisy step may pass above a failing one; the bracket is then closed
+                let low = self.passed.unwrap_or(0);
+                let mid = low + failed.saturating_sub(low) / 2;
+                if failed.saturating_sub(low) <= self.resolution || mid == 0 || mid == low
+                {
+                    None
+                }
+                else
+                {
+                    Some(mid)
+                }
+            }
+        };
+    }
+
+    // the rule behind the end of the search, once it ended
+    fn stop_reason(&self) -> Option<&str>
+    {
+        self.stopped_by.as_ref().filter(|_| self.next.is_none()).map(|fired| fired.message.as_str())
+    }
+}
+
+// The search of the group in progress, saved in the checkpoint
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Search
+{
+    Linear(LinearSweep),
+    Bisection(BisectionSearch),
+}
+
+impl Search
+{
+    pub fn new(config: &ExperimentConfig, start_tps: usize) -> Self
+    {
+        match config.search {
+            SearchStrategy::Linear => Search::Linear(LinearSweep {
+                current: start_tps,
+                max_tps: config.max_tps,
+                interval: config.tps_interval,
+                raise: config.raise_min_tps.clone(),
+                flag2s: true,
+                raised_min_tps: None,
+                stop_reason: None,
+            }),
+            SearchStrategy::Bisection => Search::Bisection(BisectionSearch::new(start_tps, config.max_tps, config.tps_interval)),
+        }
+    }
+
+    pub fn strategy(&self) -> SearchStrategy
+    {
+        match self {
+            Search::Linear(_) => SearchStrategy::Linear,
+            Search::Bisection(_) => SearchStrategy::Bisection,
+        }
+    }
+
+    fn inner(&self) -> &dyn TpsSearch
+    {
+        match self {
+            Search::Linear(search) => search,
+            Search::Bisection(search) => search,
+        }
+    }
+
+    fn inner_mut(&mut self) -> &mut dyn TpsSearch
+    {
+        match self {
+            Search::Linear(search) => search,
+            Search::Bisection(search) => search,
+        }
+    }
+
+    pub fn next_tps(&self) -> Option<usize>
+    {
+        self.inner().next_tps()
+    }
+
+    pub fn observe(&mut self, step: &StepSummary)
+    {
+        self.inner_mut().observe(step)
+    }
+
+    pub fn stop_reason(&self) -> Option<&str>
+    {
+        self.inner().stop_reason()
+    }
+
+    // The stopping rule that ended the group, if the search tracks it
+    pub fn stopped_by(&self) -> Option<&FiredRule>
+    {
+        match self {
+            Search::Linear(_) => None,
+            Search::Bisection(search) => search.stopped_by.as_ref().filter(|_| search.next.is_none()),
+        }
+    }
+
+    // Only the linear sweep raises the start of the next groups
+    pub fn raised_min_tps(&self) -> Option<usize>
+    {
+        match self {
+            Search::Linear(search) => search.raised_min_tps,
+            Search::Bisection(_) => None,
+        }
+    }
+}
+
+// The knee of a group: the highest expected TPS meeting the SLO without firing a stopping rule,
+// with the steps that show it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KneeReport
+{
+    pub group: usize,
+    pub num_shards: usize,
+    pub strategy: SearchStrategy,
+    pub slo: Slo,
+    // None if no step met the SLO
+    pub knee_tps: Option<usize>,
+    // the lowest expected TPS above the knee that violated the SLO, None if none was tested
+    pub first_violation_tps: Option<usize>,
+    pub stop_reason: Option<String>,
+    // the stopping rule that ended the group, if one did
+    #[serde(default)]
+    pub stopped_by: Option<FiredRule>,
+    // every step of the group, in the order they were run
+    pub steps: Vec<StepSummary>,
+}
+
+impl KneeReport
+{
+    pub fn new(group: usize, num_shards: usize, slo: &Slo, search: &Search, steps: Vec<StepSummary>) -> Self
+    {
+        let knee_tps = steps.iter().filter(|step| step.passes()).map(|step| step.tps).max();
+        let first_violation_tps = steps.iter()
+            .filter(|step| !step.passes() && knee_tps.map_or(true, |knee| step.tps > knee))
+            .map(|step| step.tps)
+            .min();
+        Self {
+            group,
+            num_shards,
+            strategy: search.strategy(),
+            slo: slo.clone(),
+            knee_tps,
+            first_violation_tps,
+            stop_reason: search.stop_reason().map(|reason| reason.to_string()),
+            stopped_by: search.stopped_by().cloned()
+                .or_else(|| search.stop_reason().and_then(|_| steps.last()).and_then(|step| step.stopped_by.clone())),
+            steps,
+        }
+    }
+
+    pub fn append_to(&self, folder: &Path) -> Result<(), anyhow::Error>
+    {
+        let mut file = OpenOptions::new().create(true).append(true).open(folder.join(KNEES_FILE))?;
+        writeln!(file, "{}", serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
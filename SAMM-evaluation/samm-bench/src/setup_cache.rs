@@ -0,0 +1,273 @@
+use crate::build_contract::ContractInfo;
+use crate::build_tx::{DataAndSender, TestTransactionSender};
+use crate::router::Route;
+use crate::workload::{SwapSpec, WorkloadPlan, OPERATION_LABELS};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use sui_config::SUI_GENESIS_FILENAME;
+use sui_sdk::types::base_types::{ObjectID, SuiAddress};
+use sui_sdk::types::crypto::Signature;
+use sui_sdk::types::transaction::TransactionData;
+use sui_sdk::SuiClient;
+
+// Folder of the cached setup, in the result folder
+pub const SETUP_CACHE_DIR: &str = "setup-cache";
+// The fingerprint of the cached setup, checked before anything else is read
+const FINGERPRINT_FILE: &str = "fingerprint";
+// The contracts and the pre-signed queues, in BCS
+const QUEUES_FILE: &str = "queues.bcs";
+// A copy of the ledger folder of the validator right after the setup
+const LEDGER_DIR: &str = "ledger";
+// A copy of the ledger folder right after the first genesis, before a validator ran on it
+const GENESIS_DIR: &str = "genesis";
+
+// A pre-signed transaction as stored on disk
+#[derive(Serialize, Deserialize)]
+struct StoredTx
+{
+    tx_data: TransactionData,
+    // None when it is signed with a gas pool
+    signature: Option<Signature>,
+    sender: SuiAddress,
+    gas_object: ObjectID,
+    shard: usize,
+    // the bits of send_at: BCS has no floats
+    send_at: Option<u64>,
+    operation: String,
+    swap: Option<SwapSpec>,
+    // the candidates of a routed swap
+    candidates: Option<Vec<StoredTx>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSetup
+{
+    fingerprint: u64,
+    contracts: Vec<ContractInfo>,
+    queues: Vec<Vec<StoredTx>>,
+}
+
+impl StoredTx
+{
+    fn new(tx: &DataAndSender) -> Self
+    {
+        Self {
+            tx_data: tx.tx_data.clone(),
+            signature: tx.sig.clone(),
+            sender: tx.test_sender.sender,
+            gas_object: tx.test_sender.gas_object,
+            shard: tx.shard,
+            send_at: tx.send_at.map(f64::to_bits),
+            operation: tx.operation.to_string(),
+            swap: tx.swap.clone(),
+            candidates: tx.route.as_ref().map(|route| route.candidates.iter().map(StoredTx::new).collect()),
+        }
+    }
+
+    fn into_data(self, client: &SuiClient) -> Result<DataAndSender, anyhow::Error>
+    {
+        let test_sender = TestTransactionSender::new(self.sender, self.gas_object, client.clone());
+        let mut tx = DataAndSender::unsigned(test_sender, self.tx_data);
+        tx.sig = self.signature;
+        tx.shard = self.shard;
+        tx.send_at = self.send_at.map(f64::from_bits);
+        tx.operation = OPERATION_LABELS.iter().find(|label| **label == self.operation).copied()
+            .ok_or_else(|| anyhow::anyhow!("Unknown operation {} in the cached setup", self.operation))?;
+        tx.route = match (self.candidates, &self.swap) {
+            (Some(candidates), Some(swap)) => Some(Route {
+                swap: swap.clone(),
+                candidates: candidates.into_iter().map(|candidate| candidate.into_data(client)).collect::<Result<_, _>>()?,
+            }),
+            _ => None,
+        };
+        tx.swap = self.swap;
+        Ok(tx)
+    }
+}
+
+// Bumped whenever the same inputs give another setup, or the key below changes, so that older caches never match
+const SETUP_VERSION: u32 = 1;
+
+// Everything the setup of a run depends on, hashed through its JSON serialization
+#[derive(Serialize)]
+struct SetupKey<'a>
+{
+    version: u32,
+    genesis: u64,
+    amm: &'a str,
+    addresses: &'a [SuiAddress],
+    num_contracts: usize,
+    route_swaps: bool,
+    offline: bool,
+    gas_coins: Option<usize>,
+    plan: &'a WorkloadPlan,
+}
+
+// FNV-1a, stable across builds unlike the hasher of the standard library
+fn fnv(bytes: &[u8]) -> u64
+{
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes
+    {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+// Everything the setup of a run depends on: the genesis, the AMM, the keys of the keystore, the shards, the gas pools and the planned transactions
+pub fn setup_fingerprint(genesis: u64, amm: &str, addresses: &[SuiAddress], num_contracts: usize, plan: &WorkloadPlan, route_swaps: bool,
+    offline: bool, gas_coins: Option<usize>) -> Result<u64, anyhow::Error>
+{
+    let key = SetupKey { version: SETUP_VERSION, genesis, amm, addresses, num_contracts, route_swaps, offline, gas_coins, plan };
+    Ok(fnv(&serde_json::to_vec(&key)?))
+}
+
+// Remove everything in dir, dotfiles included, but not dir itself: it may be a mount point
+fn clear_dir(dir: &Path) -> Result<(), anyhow::Error>
+{
+    std::fs::create_dir_all(dir)?;
+    for entry in std::fs::read_dir(dir)?
+    {
+        let path = entry?.path();
+        if path.is_dir() && !path.is_symlink()
+        {
+            std::fs::remove_dir_all(&path)?;
+        }
+        else
+        {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+// Copy the content of from into to, which is created if needed
+fn copy_dir(from: &Path, to: &Path) -> Result<(), anyhow::Error>
+{
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)?
+    {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir()
+        {
+            copy_dir(&entry.path(), &target)?;
+        }
+        else if file_type.is_symlink()
+        {
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &target)?;
+        }
+        else
+        {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+// Replace the content of to by a copy of the content of from
+fn replace_dir(from: &Path, to: &Path) -> Result<(), anyhow::Error>
+{
+    clear_dir(to)?;
+    copy_dir(from, to)
+}
+
+// The genesis pinned by the first run, from which every run starts, and the ledger and the pre-signed
+// queues of the last run right after its setup, reused by the next runs with the same fingerprint
+// instead of a new setup
+pub struct SetupCache
+{
+    pub dir: PathBuf,
+}
+
+impl SetupCache
+{
+    pub fn new(dir: PathBuf) -> Self
+    {
+        Self { dir }
+    }
+
+    // Keep the ledger folder of a new genesis: its keys, validator configs and genesis blob
+    pub fn pin_genesis(&self, ledger: &Path) -> Result<(), anyhow::Error>
+    {
+        let pinned = self.dir.join(GENESIS_DIR);
+        let copy = self.dir.join(format!("{}.tmp", GENESIS_DIR));
+        // an interrupted copy must not be pinned
+        replace_dir(ledger, &copy)?;
+        if pinned.exists()
+        {
+            std::fs::remove_dir_all(&pinned)?;
+        }
+        std::fs::rename(&copy, &pinned)?;
+        Ok(())
+    }
+
+    // Replace the ledger folder by the pinned genesis; false if no genesis is pinned yet
+    pub fn restore_genesis(&self, ledger: &Path) -> Result<bool, anyhow::Error>
+    {
+        let pinned = self.dir.join(GENESIS_DIR);
+        if !pinned.is_dir()
+        {
+            return Ok(false);
+        }
+        replace_dir(&pinned, ledger)?;
+        Ok(true)
+    }
+
+    // The fingerprint of the genesis blob of the pinned genesis, None if no genesis is pinned yet
+    pub fn genesis_fingerprint(&self) -> Result<Option<u64>, anyhow::Error>
+    {
+        let blob = self.dir.join(GENESIS_DIR).join(SUI_GENESIS_FILENAME);
+        if !blob.is_file()
+        {
+            return Ok(None);
+        }
+        Ok(Some(fnv(&std::fs::read(&blob)?)))
+    }
+
+    // Whether the cache holds a setup of this fingerprint
+    pub fn matches(&self, fingerprint: u64) -> bool
+    {
+        self.dir.join(LEDGER_DIR).is_dir()
+            && self.dir.join(QUEUES_FILE).is_file()
+            && std::fs::read_to_string(self.dir.join(FINGERPRINT_FILE)).map_or(false, |stored| stored.trim() == fingerprint.to_string())
+    }
+
+    // Store the setup: the ledger folder must not be written meanwhile (the validator is stopped)
+    pub fn save(&self, fingerprint: u64, ledger: &Path, contracts: &[ContractInfo], queues: &[Vec<DataAndSender>]) -> Result<(), anyhow::Error>
+    {
+        // an interrupted save must not match
+        let _ = std::fs::remove_file(self.dir.join(FINGERPRINT_FILE));
+        replace_dir(ledger, &self.dir.join(LEDGER_DIR))?;
+        let stored = StoredSetup {
+            fingerprint,
+            contracts: contracts.to_vec(),
+            queues: queues.iter().map(|queue| queue.iter().map(StoredTx::new).collect()).collect(),
+        };
+        std::fs::write(self.dir.join(QUEUES_FILE), bcs::to_bytes(&stored)?)?;
+        std::fs::write(self.dir.join(FINGERPRINT_FILE), fingerprint.to_string())?;
+        Ok(())
+    }
+
+    // Replace the ledger folder by the cached one
+    pub fn restore_ledger(&self, ledger: &Path) -> Result<(), anyhow::Error>
+    {
+        replace_dir(&self.dir.join(LEDGER_DIR), ledger)
+    }
+
+    // The queue of each client and the contracts, sent through client
+    pub fn load(&self, client: &SuiClient, fingerprint: u64) -> Result<(Vec<Vec<DataAndSender>>, Vec<ContractInfo>), anyhow::Error>
+    {
+        let stored: StoredSetup = bcs::from_bytes(&std::fs::read(self.dir.join(QUEUES_FILE))?)?;
+        if stored.fingerprint != fingerprint
+        {
+            return Err(anyhow::anyhow!("The cached setup in {} is of another run", self.dir.display()));
+        }
+        let queues = stored.queues.into_iter()
+            .map(|queue| queue.into_iter().map(|tx| tx.into_data(client)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((queues, stored.contracts))
+    }
+}
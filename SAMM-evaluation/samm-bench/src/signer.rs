@@ -0,0 +1,141 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use shared_crypto::intent::Intent;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock, RwLock};
+use sui_config::{sui_config_dir, SUI_KEYSTORE_FILENAME};
+use sui_keys::keystore::{AccountKeystore, FileBasedKeystore, InMemKeystore};
+use sui_sdk::types::base_types::SuiAddress;
+use sui_sdk::types::crypto::{get_key_pair_from_rng, Signature, SignatureScheme::ED25519, SuiKeyPair};
+use sui_sdk::types::transaction::TransactionData;
+
+// Signer shared by every TestTransactionSender of the process
+static SHARED_SIGNER: OnceLock<Arc<Signer>> = OnceLock::new();
+
+// Signs transactions with the keys of the keystore file, read once instead of for every signature,
+// and with ephemeral keys that only live in memory
+pub struct Signer
+{
+    path: PathBuf,
+    // loaded on the first signature, and again when it lacks the sender (the wallet may add keys)
+    keystore: RwLock<Option<FileBasedKeystore>>,
+    ephemeral: RwLock<InMemKeystore>,
+}
+
+impl Signer
+{
+    // A signer of the keystore at path, read when it first signs
+    pub fn new(path: PathBuf) -> Self
+    {
+        Self {
+            path,
+            keystore: RwLock::new(None),
+            ephemeral: RwLock::new(InMemKeystore::default()),
+        }
+    }
+
+    // The signer of the keystore of the sui client, shared by the whole process
+    pub fn shared() -> Arc<Signer>
+    {
+        SHARED_SIGNER.get_or_init(|| {
+            let path = sui_config_dir().map(|dir| dir.join(SUI_KEYSTORE_FILENAME)).unwrap_or_else(|_| PathBuf::from(SUI_KEYSTORE_FILENAME));
+            Arc::new(Signer::new(path))
+        }).clone()
+    }
+
+    // Create a key that is never written to disk, and return its address
+    pub fn generate_ephemeral(&self) -> Result<SuiAddress, anyhow::Error>
+    {
+        let (address, _, _) = self.ephemeral.write().unwrap().generate_and_add_new_key(ED25519, None, None)?;
+        Ok(address)
+    }
+
+    // count ephemeral addresses, in a stable order: the keys are only generated when fewer exist
+    pub fn ephemeral_addresses(&self, count: usize) -> Result<Vec<SuiAddress>, anyhow::Error>
+    {
+        while self.ephemeral.read().unwrap().addresses().len() < count
+        {
+            self.generate_ephemeral()?;
+        }
+        let mut addresses = self.ephemeral.read().unwrap().addresses();
+        addresses.sort();
+        addresses.truncate(count);
+        Ok(addresses)
+    }
+
+    // count ephemeral addresses whose keys derive from seed: the same addresses in every process
+    pub fn seeded_addresses(&self, count: usize, seed: u64) -> Result<Vec<SuiAddress>, anyhow::Error>
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut ephemeral = self.ephemeral.write().unwrap();
+        let known = ephemeral.addresses();
+        let mut addresses = vec![];
+        for _ in 0..count
+        {
+            let keypair = SuiKeyPair::Ed25519(get_key_pair_from_rng(&mut rng).1);
+            let address = SuiAddress::from(&keypair.public());
+            if !known.contains(&address)
+            {
+                ephemeral.add_key(keypair)?;
+            }
+            addresses.push(address);
+        }
+        Ok(addresses)
+    }
+
+    pub fn addresses(&self) -> Result<Vec<SuiAddress>, anyhow::Error>
+    {
+        self.load(false)?;
+        let mut addresses = self.keystore.read().unwrap().as_ref().map(|keystore| keystore.addresses()).unwrap_or_default();
+        addresses.extend(self.ephemeral.read().unwrap().addresses());
+        Ok(addresses)
+    }
+
+    // Read the keystore file, unless it is loaded and reload is false
+    fn load(&self, reload: bool) -> Result<(), anyhow::Error>
+    {
+        if !reload && self.keystore.read().unwrap().is_some()
+        {
+            return Ok(());
+        }
+        let keystore = FileBasedKeystore::new(&self.path)?;
+        *self.keystore.write().unwrap() = Some(keystore);
+        Ok(())
+    }
+
+    fn sign_with_file(&self, sender: &SuiAddress, tx_data: &TransactionData) -> Option<Result<Signature, anyhow::Error>>
+    {
+        let keystore = self.keystore.read().unwrap();
+        let keystore = keystore.as_ref().filter(|keystore| keystore.addresses().contains(sender))?;
+        Some(keystore.sign_secure(sender, tx_data, Intent::sui_transaction()).map_err(anyhow::Error::from))
+    }
+
+    // Sign a transaction of sender
+    pub fn sign(&self, sender: &SuiAddress, tx_data: &TransactionData) -> Result<Signature, anyhow::Error>
+    {
+        {
+            let ephemeral = self.ephemeral.read().unwrap();
+            if ephemeral.addresses().contains(sender)
+            {
+                return Ok(ephemeral.sign_secure(sender, tx_data, Intent::sui_transaction())?);
+            }
+        }
+        self.load(false)?;
+        if let Some(signature) = self.sign_with_file(sender, tx_data)
+        {
+            return signature;
+        }
+        self.load(true)?;
+        self.sign_with_file(sender, tx_data)
+            .unwrap_or_else(|| Err(anyhow::anyhow!("No key of {} in {}", sender, self.path.display())))
+    }
+
+    // Sign many transactions on the rayon pool, in the order given
+    pub fn sign_batch(&self, txs: &[(SuiAddress, &TransactionData)]) -> Result<Vec<Signature>, anyhow::Error>
+    {
+        // load once before the workers read it
+        self.load(false)?;
+        txs.par_iter().map(|(sender, tx_data)| self.sign(sender, tx_data)).collect()
+    }
+}
@@ -0,0 +1,132 @@
+use crate::metrics::{fmt_opt, LatencyHistogram};
+use serde::{Deserialize, Serialize};
+
+// A condition ending the search of a group, checked after every expected TPS.
+// Declared in the experiment file as [[stop_rules]] tables, e.g.
+//   [[stop_rules]]
+//   rule = "percentile_latency"
+//   percentile = 0.99
+//   max = 5.0
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum StopRule
+{
+    // failed over successful transactions above max (always fires without a successful transaction)
+    FailureRatio { max: f64 },
+    // mean latency above max seconds (fires when a repetition had no successful transaction)
+    MeanLatency { max: f64 },
+    // latency at the percentile above max seconds
+    PercentileLatency { percentile: f64, max: f64 },
+    // successful transactions over the expected ones (target TPS * test time) below min
+    Throughput { min: f64 },
+    // sui-test-validator exited during a repetition
+    ValidatorCrash,
+}
+
+// The thresholds that used to be written in the sweep loop
+pub fn default_stop_rules() -> Vec<StopRule>
+{
+    vec![
+        StopRule::FailureRatio { max: 0.1 },
+        StopRule::MeanLatency { max: 10.0 },
+        StopRule::Throughput { min: 0.8 },
+        StopRule::ValidatorCrash,
+    ]
+}
+
+// What a step looked like to the rules
+pub struct StepObservation<'a>
+{
+    pub success: usize,
+    pub fail: usize,
+    pub mean_latency: Option<f64>,
+    pub success_ratio: f64,
+    pub histogram: &'a LatencyHistogram,
+    pub validator_crashed: bool,
+}
+
+// The rule that ended a group and the value that triggered it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FiredRule
+{
+    pub rule: StopRule,
+    pub value: Option<f64>,
+    pub message: String,
+}
+
+impl StopRule
+{
+    pub fn check(&self, step: &StepObservation) -> Option<FiredRule>
+    {
+        let fired = |value: Option<f64>, message: String| Some(FiredRule { rule: self.clone(), value, message });
+        match self {
+            StopRule::FailureRatio { max } => {
+                if step.success == 0
+                {
+                    return fired(None, "No successful execution!".to_string());
+                }
+                let ratio = step.fail as f64 / step.success as f64;
+                if ratio > *max
+                {
+                    return fired(Some(ratio), format!("Too much failures! (failure ratio {} > {})", ratio, max));
+                }
+                None
+            }
+            StopRule::MeanLatency { max } => match step.mean_latency {
+                None => fired(None, "No successful execution in a repetition!".to_string()),
+                Some(latency) if latency > *max => fired(Some(latency), format!("Too large lantencies! (mean {} s > {} s)", latency, max)),
+                Some(_) => None,
+            },
+            StopRule::PercentileLatency { percentile, max } => {
+                let latency = step.histogram.value_at_quantile(*percentile);
+                if latency.map_or(true, |latency| latency > *max)
+                {
+                    return fired(latency, format!("Too large lantencies! (p{} {} s > {} s)", percentile * 100.0, fmt_opt(latency), max));
+                }
+                None
+            }
+            StopRule::Throughput { min } => {
+                if step.success_ratio < *min
+                {
+                    return fired(Some(step.success_ratio), format!("Not enough successful executions! ({} of the expected < {})", step.success_ratio, min));
+                }
+                None
+            }
+            StopRule::ValidatorCrash => {
+                if step.validator_crashed
+                {
+                    return fired(None, "Sui-test-validator crashed during the test!".to_string());
+                }
+                None
+            }
+        }
+    }
+}
+
+// The first rule firing on the step, in the declared order
+pub fn evaluate(rules: &[StopRule], step: &StepObservation) -> Option<FiredRule>
+{
+    rules.iter().find_map(|rule| rule.check(step))
+}
+
+// Raise the min_tps of the next groups to this TPS while every step of the group so far had
+// a mean latency below max_mean_latency and a success ratio above min_success_ratio.
+// A max_mean_latency of 0 disables it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RaiseMinTps
+{
+    pub max_mean_latency: f64,
+    pub min_success_ratio: f64,
+}
+
+impl Default for RaiseMinTps
+{
+    fn default() -> Self
+    {
+        Self {
+            max_mean_latency: 1.75,
+            min_success_ratio: 0.8,
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use crate::backend::SWAP_AMOUNT;
+use crate::build_contract::COIN_EACH_OBJ;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, LogNormal, Pareto};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// Largest swap argument: the input coin of a swap (COIN_EACH_OBJ) must still cover it with the fee
+pub const MAX_SWAP_AMOUNT: u64 = (COIN_EACH_OBJ / 2) as u64;
+
+// Direction of a swap, named by the token sold. The Move pool is Pool<USDT, XBTC>, so USDT is its X
+// and XBTC its Y. Traces may still use the former names x_to_y (XBTC to USDT) and y_to_x.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapDirection
+{
+    #[serde(alias = "x_to_y")]
+    XbtcToUsdt,
+    #[serde(alias = "y_to_x")]
+    UsdtToXbtc,
+}
+
+// A swap to sign: its direction and its argument (the output amount for SAMM, the minimal output for OmniSwap)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SwapSpec
+{
+    pub direction: SwapDirection,
+    pub amount: u64,
+}
+
+// What a transaction does in its pool. Every operation is an entry function of the interface module
+// of the AMM, and all of them write the shared Global object.
+#[derive(Clone, Debug, Serialize)]
+pub enum Operation
+{
+    Swap(SwapSpec),
+    // multi_swap with a vector of one input coin
+    MultiSwap(SwapSpec),
+    // add_liquidity of one coin of COIN_EACH_OBJ of each token
+    AddLiquidity,
+    // multi_add_liquidity with vectors of one coin of each token
+    MultiAddLiquidity,
+    // remove_liquidity of an LP coin minted by the data builder
+    RemoveLiquidity,
+    // multi_remove_liquidity with a vector of one LP coin
+    MultiRemoveLiquidity,
+}
+
+// The label of every operation
+pub const OPERATION_LABELS: [&str; 6] = ["swap", "multi_swap", "add_liquidity", "multi_add_liquidity", "remove_liquidity", "multi_remove_liquidity"];
+
+impl Operation
+{
+    // Name of the entry function, also used to label the results
+    pub fn label(&self) -> &'static str
+    {
+        match self {
+            Operation::Swap(_) => "swap",
+            Operation::MultiSwap(_) => "multi_swap",
+            Operation::AddLiquidity => "add_liquidity",
+            Operation::MultiAddLiquidity => "multi_add_liquidity",
+            Operation::RemoveLiquidity => "remove_liquidity",
+            Operation::MultiRemoveLiquidity => "multi_remove_liquidity",
+        }
+    }
+
+    // The swap of a swap or multi_swap, the operations a router may send to another shard
+    pub fn swap(&self) -> Option<&SwapSpec>
+    {
+        match self {
+            Operation::Swap(swap) | Operation::MultiSwap(swap) => Some(swap),
+            _ => None,
+        }
+    }
+
+    // The coins the transaction pays with ("XBTC", "USDT" or "LP"), in the order of the call arguments
+    pub fn coin_names(&self) -> Vec<&'static str>
+    {
+        match self {
+            Operation::Swap(swap) | Operation::MultiSwap(swap) => match swap.direction {
+                SwapDirection::XbtcToUsdt => vec!["XBTC"],
+                SwapDirection::UsdtToXbtc => vec!["USDT"],
+            },
+            // the pool was created with the type arguments <USDT, XBTC>
+            Operation::AddLiquidity | Operation::MultiAddLiquidity => vec!["USDT", "XBTC"],
+            Operation::RemoveLiquidity | Operation::MultiRemoveLiquidity => vec!["LP"],
+        }
+    }
+}
+
+// One transaction of the workload, before its coins are minted and it is signed
+#[derive(Clone, Debug, Serialize)]
+pub struct PlannedTx
+{
+    pub shard: usize,
+    pub op: Operation,
+    // when to send it (seconds since the start of the run); None lets the arrival process decide
+    pub send_at: Option<f64>,
+}
+
+// The transactions of each client, in sending order
+pub type WorkloadPlan = Vec<Vec<PlannedTx>>;
+
+// Distribution of the swap argument, clamped to [1, MAX_SWAP_AMOUNT]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum AmountDistribution
+{
+    Fixed { amount: u64 },
+    Uniform { min: u64, max: u64 },
+    // exp(N(ln median, sigma^2))
+    LogNormal { median: f64, sigma: f64 },
+    // heavy tail: P(amount > x) = (scale / x)^shape for x >= scale
+    Pareto { scale: f64, shape: f64 },
+}
+
+impl Default for AmountDistribution
+{
+    fn default() -> Self
+    {
+        AmountDistribution::Fixed { amount: SWAP_AMOUNT }
+    }
+}
+
+impl AmountDistribution
+{
+    pub fn validate(&self) -> Result<(), anyhow::Error>
+    {
+        let valid = match self {
+            AmountDistribution::Fixed { amount } => *amount > 0,
+            AmountDistribution::Uniform { min, max } => *min > 0 && min <= max,
+            AmountDistribution::LogNormal { median, sigma } => *median > 0.0 && *sigma >= 0.0,
+            AmountDistribution::Pareto { scale, shape } => *scale > 0.0 && *shape > 0.0,
+        };
+        if !valid
+        {
+            return Err(anyhow::anyhow!("Invalid amount distribution {:?}", self));
+        }
+        Ok(())
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> u64
+    {
+        let amount = match self {
+            AmountDistribution::Fixed { amount } => *amount as f64,
+            AmountDistribution::Uniform { min, max } => rng.gen_range(*min..=*max) as f64,
+            AmountDistribution::LogNormal { median, sigma } => LogNormal::new(median.ln(), *sigma).unwrap().sample(rng),
+            AmountDistribution::Pareto { scale, shape } => Pareto::new(*scale, *shape).unwrap().sample(rng),
+        };
+        (amount.round().max(1.0) as u64).min(MAX_SWAP_AMOUNT)
+    }
+}
+
+// Direction and size of the generated swaps
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SwapMix
+{
+    // fraction of XBTC->USDT swaps, the rest are USDT->XBTC
+    #[serde(alias = "x_to_y_ratio")]
+    pub xbtc_to_usdt_ratio: f64,
+    pub amount: AmountDistribution,
+}
+
+impl Default for SwapMix
+{
+    fn default() -> Self
+    {
+        Self {
+            xbtc_to_usdt_ratio: 1.0,
+            amount: AmountDistribution::default(),
+        }
+    }
+}
+
+impl SwapMix
+{
+    pub fn validate(&self) -> Result<(), anyhow::Error>
+    {
+        if !(0.0..=1.0).contains(&self.xbtc_to_usdt_ratio)
+        {
+            return Err(anyhow::anyhow!("xbtc_to_usdt_ratio must be in [0, 1]"));
+        }
+        self.amount.validate()
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> SwapSpec
+    {
+        let direction = if rng.gen::<f64>() < self.xbtc_to_usdt_ratio { SwapDirection::XbtcToUsdt } else { SwapDirection::UsdtToXbtc };
+        SwapSpec {
+            direction,
+            amount: self.amount.sample(rng),
+        }
+    }
+}
+
+// Relative weights of the operations of the generated workload, e.g. 90% swaps, 5% adds and 5% removes:
+//   [operations]
+//   swap = 0.9
+//   add_liquidity = 0.05
+//   remove_liquidity = 0.05
+// The swaps of both kinds are drawn from the swap mix.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OperationMix
+{
+    pub swap: f64,
+    pub multi_swap: f64,
+    pub add_liquidity: f64,
+    pub multi_add_liquidity: f64,
+    pub remove_liquidity: f64,
+    pub multi_remove_liquidity: f64,
+}
+
+impl Default for OperationMix
+{
+    fn default() -> Self
+    {
+        Self {
+            swap: 1.0,
+            multi_swap: 0.0,
+            add_liquidity: 0.0,
+            multi_add_liquidity: 0.0,
+            remove_liquidity: 0.0,
+            multi_remove_liquidity: 0.0,
+        }
+    }
+}
+
+impl OperationMix
+{
+    fn weights(&self) -> [f64; 6]
+    {
+        [self.swap, self.multi_swap, self.add_liquidity, self.multi_add_liquidity, self.remove_liquidity, self.multi_remove_liquidity]
+    }
+
+    pub fn validate(&self) -> Result<(), anyhow::Error>
+    {
+        let weights = self.weights();
+        if weights.iter().any(|weight| !weight.is_finite() || *weight < 0.0) || weights.iter().sum::<f64>() <= 0.0
+        {
+            return Err(anyhow::anyhow!("Invalid operation mix {:?}", self));
+        }
+        Ok(())
+    }
+
+    // Only swaps: no operation is drawn, so that the plan of a seed is the same as without a mix
+    pub fn swaps_only(&self) -> bool
+    {
+        self.weights()[1..].iter().all(|weight| *weight == 0.0)
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R, swaps: &SwapMix) -> Operation
+    {
+        if self.swaps_only()
+        {
+            return Operation::Swap(swaps.sample(rng));
+        }
+        let weights = self.weights();
+        let mut draw = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        let mut index = 0;
+        while index + 1 < weights.len() && draw >= weights[index]
+        {
+            draw -= weights[index];
+            index += 1;
+        }
+        match index {
+            0 => Operation::Swap(swaps.sample(rng)),
+            1 => Operation::MultiSwap(swaps.sample(rng)),
+            2 => Operation::AddLiquidity,
+            3 => Operation::MultiAddLiquidity,
+            4 => Operation::RemoveLiquidity,
+            _ => Operation::MultiRemoveLiquidity,
+        }
+    }
+}
+
+// How the generated transactions are spread over the shards (pools) of a group, e.g.
+//   [shard_popularity]
+//   popularity = "zipf"
+//   s = 1.0
+// The total number of transactions does not change, only their share per shard.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "popularity", rename_all = "snake_case")]
+pub enum ShardPopularity
+{
+    // every shard gets the same number of transactions (the original workload)
+    #[default]
+    Uniform,
+    // shard k (from 0) has weight 1 / (k + 1)^s
+    Zipf { s: f64 },
+    // shard 0 gets `share` of the transactions, the other shards share the rest evenly
+    HotPool { share: f64 },
+    // explicit weights of shards 0, 1, ...; a group with fewer shards uses the first ones
+    Weights { weights: Vec<f64> },
+}
+
+impl ShardPopularity
+{
+    // num_shards: the number of shards of each group; a group with fewer shards than the weights uses their
+    // prefix, which must cover all of its shards and give some of them a positive weight
+    pub fn validate(&self, num_shards: &[usize]) -> Result<(), anyhow::Error>
+    {
+        let valid = match self {
+            ShardPopularity::Uniform => true,
+            ShardPopularity::Zipf { s } => s.is_finite() && *s >= 0.0,
+            ShardPopularity::HotPool { share } => (0.0..=1.0).contains(share),
+            ShardPopularity::Weights { weights } => weights.iter().all(|weight| weight.is_finite() && *weight >= 0.0)
+                && num_shards.iter().all(|n| weights.len() >= *n && weights[..*n].iter().sum::<f64>() > 0.0),
+        };
+        if !valid
+        {
+            return Err(anyhow::anyhow!("Invalid shard popularity {:?} for groups of {:?} shards", self, num_shards));
+        }
+        Ok(())
+    }
+
+    // The relative weight of each of the num_contracts shards
+    pub fn weights(&self, num_contracts: usize) -> Vec<f64>
+    {
+        match self {
+            ShardPopularity::Uniform => vec![1.0; num_contracts],
+            ShardPopularity::Zipf { s } => (0..num_contracts).map(|k| 1.0 / ((k + 1) as f64).powf(*s)).collect(),
+            ShardPopularity::HotPool { share } => {
+                if num_contracts == 1
+                {
+                    return vec![1.0];
+                }
+                let rest = (1.0 - share) / (num_contracts - 1) as f64;
+                (0..num_contracts).map(|k| if k == 0 { *share } else { rest }).collect()
+            }
+            ShardPopularity::Weights { weights } => weights.iter().take(num_contracts).cloned().collect(),
+        }
+    }
+
+    // Split `total` transactions over the shards in proportion to their weights (largest remainders),
+    // so that the counts add up to total exactly
+    pub fn counts(&self, num_contracts: usize, total: usize) -> Vec<usize>
+    {
+        let weights = self.weights(num_contracts);
+        let sum: f64 = weights.iter().sum();
+        if num_contracts == 0 || sum <= 0.0
+        {
+            return vec![0; num_contracts];
+        }
+        let quotas: Vec<f64> = weights.iter().map(|weight| weight / sum * total as f64).collect();
+        let mut counts: Vec<usize> = quotas.iter().map(|quota| quota.floor() as usize).collect();
+        let mut remainders: Vec<usize> = (0..num_contracts).collect();
+        remainders.sort_by(|a, b| (quotas[*b] - quotas[*b].floor()).total_cmp(&(quotas[*a] - quotas[*a].floor())).then(a.cmp(b)));
+        let missing = total.saturating_sub(counts.iter().sum());
+        for shard in remainders.into_iter().cycle().take(missing)
+        {
+            counts[shard] += 1;
+        }
+        counts
+    }
+}
+
+// The generated workload: coin_each_client transactions per client and contract on average, drawn from the mixes,
+// spread over the shards by their popularity and shuffled among the clients
+pub fn uniform_plan(num_clients: usize, num_contracts: usize, coin_each_client: usize, seed: u64, popularity: &ShardPopularity,
+    operations: &OperationMix, mix: &SwapMix) -> WorkloadPlan
+{
+    // nothing to deal, and chunks of zero transactions would panic
+    if num_contracts == 0 || coin_each_client == 0
+    {
+        return vec![vec![]; num_clients];
+    }
+    let counts = popularity.counts(num_contracts, num_contracts * num_clients * coin_each_client);
+    let mut shards: Vec<usize> = counts.iter().enumerate()
+        .flat_map(|(shard, count)| std::iter::repeat(shard).take(*count))
+        .collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    shards.shuffle(&mut rng);
+    shards.chunks(num_contracts * coin_each_client)
+        .map(|chunk| chunk.iter()
+            .map(|shard| PlannedTx {
+                shard: *shard,
+                op: operations.sample(&mut rng, mix),
+                send_at: None,
+            })
+            .collect())
+        .collect()
+}
+
+// Where the trace is and how its amounts map to the test coins
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceConfig
+{
+    pub path: PathBuf,
+    // multiplies the amounts of the trace to get the swap argument in the smallest unit of the test coins
+    #[serde(default = "default_amount_scale")]
+    pub amount_scale: f64,
+}
+
+fn default_amount_scale() -> f64
+{
+    1.0
+}
+
+// One row of a trace file (CSV with the header timestamp,direction,amount[,pool])
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceRecord
+{
+    // seconds, in any origin
+    pub timestamp: f64,
+    pub direction: SwapDirection,
+    pub amount: f64,
+    // pool of the original DEX; the distinct pools are spread over the shards
+    #[serde(default)]
+    pub pool: Option<String>,
+}
+
+// A recorded sequence of swaps, sorted by time
+pub struct Trace
+{
+    pub records: Vec<TraceRecord>,
+}
+
+impl Trace
+{
+    pub fn load(path: &Path) -> Result<Self, anyhow::Error>
+    {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_path(path)
+            .map_err(|e| anyhow::anyhow!("Cannot read trace {}: {}", path.display(), e))?;
+        let mut records = vec![];
+        for record in reader.deserialize()
+        {
+            let record: TraceRecord = record?;
+            records.push(record);
+        }
+        if records.is_empty()
+        {
+            return Err(anyhow::anyhow!("Trace {} has no swap", path.display()));
+        }
+        records.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Ok(Self { records })
+    }
+
+    // Replay the trace at target_tps for `duration` seconds, starting over when it ends.
+    // The timestamps are rescaled so that the trace takes records / target_tps seconds;
+    // the swaps are dealt to the clients in turn. A trace only holds swaps, the operation mix does not apply.
+    pub fn plan(&self, config: &TraceConfig, target_tps: usize, num_clients: usize, num_contracts: usize, duration: f64) -> WorkloadPlan
+    {
+        let mut plan: WorkloadPlan = vec![vec![]; num_clients];
+        if target_tps == 0
+        {
+            return plan;
+        }
+        let n = self.records.len();
+        let first = self.records[0].timestamp;
+        let span = self.records[n - 1].timestamp - first;
+        // the trace plus one average gap, so that consecutive replays do not overlap
+        let length = if n > 1 && span > 0.0 { span + span / (n - 1) as f64 } else { 1.0 };
+        let period = n as f64 / target_tps as f64;
+        let mut pools: HashMap<&str, usize> = HashMap::new();
+        let mut index = 0;
+        'replay: for replay in 0usize..
+        {
+            for record in &self.records
+            {
+                let send_at = replay as f64 * period + (record.timestamp - first) / length * period;
+                if send_at > duration
+                {
+                    break 'replay;
+                }
+                let shard = match &record.pool {
+                    Some(pool) => {
+                        let next = pools.len();
+                        *pools.entry(pool.as_str()).or_insert(next) % num_contracts
+                    }
+                    None => index % num_contracts,
+                };
+                let (amount, _) = scale_amount(record.amount, config.amount_scale);
+                plan[index % num_clients].push(PlannedTx {
+                    shard,
+                    op: Operation::Swap(SwapSpec { direction: record.direction, amount }),
+                    send_at: Some(send_at),
+                });
+                index += 1;
+            }
+        }
+        plan
+    }
+
+    // The number of swaps of the trace whose scaled amount is clamped to [1, MAX_SWAP_AMOUNT] when it is replayed
+    pub fn clamped(&self, config: &TraceConfig) -> usize
+    {
+        self.records.iter().filter(|record| scale_amount(record.amount, config.amount_scale).1).count()
+    }
+}
+
+// The swap argument of an amount of the trace, and whether it was clamped
+fn scale_amount(amount: f64, amount_scale: f64) -> (u64, bool)
+{
+    let scaled = (amount * amount_scale).round();
+    ((scaled.max(1.0) as u64).min(MAX_SWAP_AMOUNT), !(1.0..=MAX_SWAP_AMOUNT as f64).contains(&scaled))
+}
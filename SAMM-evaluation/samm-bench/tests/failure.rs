@@ -0,0 +1,76 @@
+// The classification of failures, fed with error messages as sui-sdk and the effects render them.
+use samm_bench::failure::{parse_move_abort, FailureClass};
+
+const SWAP_ABORT: &str = "MoveAbort(MoveLocation { module: ModuleId { address: 4e1c0a5c2d3b8f9e7a6b5c4d3e2f1a0b9c8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f, \
+    name: Identifier(\"implements\") }, function: 12, instruction: 31, function_name: Some(\"swap_out\") }, 7) in command 0";
+
+fn abort(module: &str, code: u64) -> FailureClass
+{
+    FailureClass::MoveAbort { module: module.to_string(), code }
+}
+
+#[test]
+fn move_abort_gives_module_and_code()
+{
+    assert_eq!(parse_move_abort(SWAP_ABORT), Some(abort("implements", 7)));
+    let interface = "MoveAbort(MoveLocation { module: ModuleId { address: 0x2, name: Identifier(\"interface\") }, function: 3, \
+        instruction: 9, function_name: Some(\"add_liquidity\") }, 105) in command 1";
+    assert_eq!(parse_move_abort(interface), Some(abort("interface", 105)));
+    assert_eq!(parse_move_abort("InsufficientGas"), None);
+    assert_eq!(FailureClass::from_execution_error(SWAP_ABORT).label(), "move_abort:implements:7:ERR_COIN_OUT_NUM_LESS_THAN_EXPECTED_MINIMUM");
+}
+
+#[test]
+fn execution_errors()
+{
+    assert_eq!(FailureClass::from_execution_error(SWAP_ABORT), abort("implements", 7));
+    assert_eq!(FailureClass::from_execution_error("InsufficientGas"), FailureClass::InsufficientGas);
+    assert_eq!(FailureClass::from_execution_error("InputObjectDeleted"), FailureClass::ObjectConflict);
+    assert_eq!(FailureClass::from_execution_error("InvariantViolation"), FailureClass::Execution);
+}
+
+// An object id and a digest holding "429" and "Busy" must not make a lock conflict an overload
+#[test]
+fn lock_conflict_with_digits_in_its_ids()
+{
+    let message = "RPC error: Call(Custom(ErrorObject { code: ServerError(-32002), message: \"Transaction execution failed due to issues \
+        with transaction inputs, please review the errors and try again: Object (0x5a4291c0b8e1d7f8a429e3c3b2a1d0f9e8c7b6a5a4d3c2b1a0f9e8d7c6b5a429, \
+        SequenceNumber(12), o#4299BusyXq7bVnkKLt3mwQ9yRcsPeHjAzG5fD2uTaW8) is not available for consumption, its current version: \
+        SequenceNumber(13)\", data: None }))";
+    assert_eq!(FailureClass::from_submit_error(message), FailureClass::ObjectConflict);
+    let equivocation = "Failed to sign transaction by a quorum of validators because of locked objects: \
+        [(0x429b, SequenceNumber(3), o#429Busy4kX8v)], retried a conflicting transaction Some(TransactionDigest(429zBusyQ)), success: Some(true)";
+    assert_eq!(FailureClass::from_submit_error(equivocation), FailureClass::ObjectConflict);
+}
+
+#[test]
+fn submit_errors()
+{
+    let cases = [
+        ("RPC error: Call(Custom(ErrorObject { code: ServerError(-32050), message: \"Transaction timed out before reaching finality\", data: None }))",
+            FailureClass::RpcTimeout),
+        ("RPC error: Transport(Request timeout)", FailureClass::RpcTimeout),
+        ("RPC error: Transport(Request rejected `429`)", FailureClass::Overload),
+        ("RPC error: Transport(HTTP error: 429 Too Many Requests)", FailureClass::Overload),
+        ("Transaction is not processed because 6667 of validators by stake are overloaded with certificates pending execution.",
+            FailureClass::Overload),
+        ("Balance of gas object 10 is lower than the needed amount: 190000000.", FailureClass::InsufficientGas),
+        ("RPC error: Transport(Connection refused (os error 111))", FailureClass::Rpc),
+        // a digest alone is no status code
+        ("RPC error: Call(Custom(ErrorObject { code: InvalidParams, message: \"Invalid digest 429Busy7Tq\", data: None }))", FailureClass::Rpc),
+    ];
+    for (message, class) in cases
+    {
+        assert_eq!(FailureClass::from_submit_error(message), class, "{}", message);
+    }
+    assert_eq!(FailureClass::from_submit_error(&format!("Error executing transaction: {}", SWAP_ABORT)), abort("implements", 7));
+}
+
+#[test]
+fn labels_round_trip()
+{
+    for class in [FailureClass::Overload, FailureClass::ObjectConflict, FailureClass::Client, abort("math", 501), abort("other", 3)]
+    {
+        assert_eq!(FailureClass::from_label(&class.label()), Some(class));
+    }
+}
@@ -0,0 +1,178 @@
+// Properties of the off-chain model of the pools, which mirrors the Move code of ../samm and ../omniswap:
+// a change of the fee constants or of the formulas there should be made to the model and checked here.
+use proptest::prelude::*;
+use samm_bench::model::*;
+
+// Reserves large enough for the initial liquidity and small enough for the pool limit
+fn reserve() -> impl Strategy<Value = u64>
+{
+    1_000_000u64..MAX_POOL_VALUE / 4
+}
+
+// The term 1.5 * RATIO_MULTIPLIER * coin_out / coin_out_reserve of the fee ratio
+fn ratio_term(coin_out: u64, coin_out_reserve: u64) -> u64
+{
+    (1500 * coin_out as u128 / coin_out_reserve as u128) as u64
+}
+
+// Input paid by buying coin_out from the pool in `pieces` swaps one after the other
+fn samm_cost_in_pieces(pool: &mut PoolModel, x_in: bool, coin_out: u64, pieces: u64) -> u128
+{
+    (0..pieces)
+        .map(|_| pool.swap_samm(x_in, u64::MAX, coin_out / pieces).unwrap().amount_in as u128)
+        .sum()
+}
+
+proptest! {
+    // get_multiplier: the fee ratio of an input amount is between MIN_FEE_RATIO and MAX_FEE_RATIO
+    #[test]
+    fn fee_ratio_in_within_bounds(coin_in in 1u64..u64::MAX, reserve in 1u64..u64::MAX)
+    {
+        if let Ok(ratio) = get_multiplier(coin_in, reserve, reserve)
+        {
+            prop_assert!(ratio <= MAX_FEE_RATIO);
+            let fac1 = RATIO_MULTIPLIER / sqrt_64(reserve / coin_in).max(1);
+            if fac1 <= MAX_FEE_RATIO - MIN_FEE_RATIO || fac1 > MAX_FEE_RATIO
+            {
+                prop_assert!(ratio >= MIN_FEE_RATIO);
+            }
+        }
+    }
+
+    // get_multiplier_out: the fee ratio of an output amount is between MIN_FEE_RATIO and MAX_FEE_RATIO,
+    // except in the gap documented on fee_ratio_out, and decreases with the output
+    #[test]
+    fn fee_ratio_out_within_bounds(coin_out in 0u64..u64::MAX / 2000, reserve in 1u64..u64::MAX)
+    {
+        let ratio = fee_ratio_out(coin_out, reserve).unwrap();
+        prop_assert!(ratio <= MAX_FEE_RATIO);
+        let term = ratio_term(coin_out, reserve);
+        if term <= MAX_FEE_RATIO - MIN_FEE_RATIO
+        {
+            prop_assert_eq!(ratio, MAX_FEE_RATIO - term);
+            prop_assert!(ratio >= MIN_FEE_RATIO);
+            if coin_out > 0
+            {
+                prop_assert!(fee_ratio_out(coin_out - 1, reserve).unwrap() >= ratio);
+            }
+        }
+        else if term > MAX_FEE_RATIO
+        {
+            prop_assert_eq!(ratio, MIN_FEE_RATIO);
+        }
+    }
+
+    // get_multiplier_out is the fee ratio at the price of the pool
+    #[test]
+    fn fee_multiplier_out_is_ratio_times_price(coin_out in 0u64..1u64 << 40, reserve_in in reserve(), reserve_out in reserve())
+    {
+        let ratio = fee_ratio_out(coin_out, reserve_out).unwrap();
+        let multiplier = get_multiplier_out(coin_out, reserve_in, reserve_out).unwrap();
+        prop_assert_eq!(multiplier as u128, ratio as u128 * reserve_in as u128 / reserve_out as u128);
+    }
+
+    // A swap that completes increases k; one that aborts leaves the pool unchanged
+    #[test]
+    fn samm_swap_never_decreases_k(x in reserve(), y in reserve(), x_in: bool, coin_in_max: u64, coin_out in 1u64..1u64 << 50)
+    {
+        let mut pool = PoolModel::new(x, y).unwrap();
+        let before = pool;
+        match pool.swap_samm(x_in, coin_in_max, coin_out) {
+            Ok(outcome) => {
+                prop_assert!(pool.k() > before.k());
+                prop_assert!(outcome.amount_in <= coin_in_max);
+                prop_assert!(outcome.fee <= outcome.amount_in);
+            }
+            // tiny outputs may abort with ERR_INCORRECT_SWAP: their fee rounds to zero and the
+            // rounding of the constant-product part alone can lower k
+            Err(_) => prop_assert_eq!(pool, before),
+        }
+    }
+
+    #[test]
+    fn cpmm_swap_never_decreases_k(x in reserve(), y in reserve(), x_in: bool, coin_in in 1u64..1u64 << 50, coin_out_min: u64)
+    {
+        let mut pool = PoolModel::new(x, y).unwrap();
+        let before = pool;
+        match pool.swap_cpmm(x_in, coin_in, coin_out_min) {
+            Ok(outcome) => {
+                prop_assert!(pool.k() > before.k());
+                prop_assert!(outcome.amount_out >= coin_out_min);
+                prop_assert_eq!(pool.fee_coin_x + pool.fee_coin_y, outcome.fee_to_fundation);
+            }
+            Err(e) => {
+                prop_assert_eq!(pool, before);
+                // the 0.3% of the CPMM always outweighs the rounding: the check of the Move code never fires
+                prop_assert_ne!(e, ModelError::Abort { module: "implements", code: ERR_INCORRECT_SWAP });
+            }
+        }
+    }
+
+    // Splitting a trade into smaller swaps on the same shard is never cheaper than the whole trade:
+    // the smaller swaps pay a higher fee ratio, at a price moved by the earlier ones.
+    // Each swap may round down by a few units.
+    #[test]
+    fn samm_split_trade_is_not_cheaper(r in 10_000_000u64..MAX_POOL_VALUE / 4, x_in: bool, share in 1u64..=60, pieces in 2u64..8)
+    {
+        // an output of at most 6% of the reserve keeps the fee ratio out of the gap
+        let coin_out = r / 1000 * share / pieces * pieces;
+        prop_assume!(coin_out > 0);
+        let mut whole = PoolModel::new(r, r).unwrap();
+        let mut split = whole;
+        let cost = whole.swap_samm(x_in, u64::MAX, coin_out).unwrap().amount_in as u128;
+        let split_cost = samm_cost_in_pieces(&mut split, x_in, coin_out, pieces);
+        let rounding = pieces as u128 * (3 + (coin_out / pieces / FEE_SCALE) as u128);
+        prop_assert!(cost <= split_cost + rounding, "whole {} split {}", cost, split_cost);
+    }
+
+    // Splitting a trade evenly over n shards of 1/n of the liquidity costs what the trade costs
+    // on one pool of all the liquidity, up to the rounding of each swap
+    #[test]
+    fn samm_split_over_shards_is_not_cheaper(r in 10_000_000u64..1u64 << 50, x_in: bool, share in 1u64..=60, shards in 2u64..8)
+    {
+        let coin_out = r / 1000 * share;
+        prop_assume!(coin_out > 0);
+        let mut whole = PoolModel::new(r * shards, r * shards).unwrap();
+        let cost = whole.swap_samm(x_in, u64::MAX, coin_out * shards).unwrap().amount_in as u128;
+        let mut split_cost = 0u128;
+        for _ in 0..shards
+        {
+            let mut shard = PoolModel::new(r, r).unwrap();
+            split_cost += shard.swap_samm(x_in, u64::MAX, coin_out).unwrap().amount_in as u128;
+        }
+        let rounding = shards as u128 * (3 + (coin_out / FEE_SCALE) as u128);
+        prop_assert!(cost <= split_cost + rounding, "whole {} split {}", cost, split_cost);
+    }
+
+    // Near the u64 limits the model reports an abort like the Move code instead of overflowing
+    #[test]
+    fn pricing_never_overflows(a: u64, b: u64, c: u64, fee in 0u64..=FEE_SCALE)
+    {
+        let _ = mul_div(a, b, c);
+        let _ = mul_div_u128(a as u128, b as u128, c as u128);
+        let _ = get_fee_to_fundation(a);
+        let _ = get_multiplier(a, b, c);
+        let _ = get_multiplier_out(a, b, c);
+        let _ = get_amount_in_samm(a, b, c, fee);
+        let _ = get_amount_out_samm(a, b, c, fee);
+        if let Ok(out) = get_amount_out(a, b, c)
+        {
+            prop_assert!(out <= c);
+        }
+        let root = sqrt(a as u128 * b as u128) as u128;
+        prop_assert!(root * root <= a as u128 * b as u128);
+    }
+
+    #[test]
+    fn swaps_near_the_pool_limit_never_overflow(x in 1u64..u64::MAX, y in 1u64..u64::MAX, x_in: bool, a: u64, b: u64)
+    {
+        let pool = PoolModel { coin_x: x, coin_y: y, lp_supply: MINIMAL_LIQUIDITY, ..Default::default() };
+        let _ = { let mut p = pool; p.swap_samm(x_in, a, b) };
+        if let Err(e) = { let mut p = pool; p.swap_cpmm(x_in, a, b) }
+        {
+            prop_assert_ne!(e, ModelError::Abort { module: "implements", code: ERR_INCORRECT_SWAP });
+        }
+        let _ = { let mut p = pool; p.add_liquidity(a, 1, b, 1) };
+        let _ = { let mut p = pool; p.remove_liquidity(a) };
+    }
+}
@@ -0,0 +1,132 @@
+// The bisection search driven by a scripted oracle instead of runs: a step passes when its TPS is
+// at most the knee of the script.
+use proptest::prelude::*;
+use samm_bench::search::{BisectionSearch, KneeReport, Search, Slo, StepSummary, TpsSearch};
+use samm_bench::stopping::{FiredRule, StopRule};
+
+fn fired(rule: StopRule) -> FiredRule
+{
+    FiredRule { message: format!("{:?} fired", rule), rule, value: None }
+}
+
+fn step(tps: usize, meets_slo: bool, stopped_by: Option<FiredRule>) -> StepSummary
+{
+    StepSummary {
+        tps,
+        repetitions: 1,
+        success: 0,
+        fail: 0,
+        mean_latency: None,
+        percentile_latency: None,
+        success_ratio: 0.0,
+        meets_slo,
+        stopped_by,
+    }
+}
+
+// Run the search to its end with the oracle, and return the steps it ran
+fn run(search: &mut BisectionSearch, oracle: impl Fn(usize) -> StepSummary) -> Vec<StepSummary>
+{
+    let mut steps = vec![];
+    while let Some(tps) = search.next_tps()
+    {
+        assert!(tps > 0 && tps <= search.max_tps, "tried {} TPS", tps);
+        assert!(steps.len() < 100, "the search does not converge");
+        let step = oracle(tps);
+        search.observe(&step);
+        steps.push(step);
+    }
+    steps
+}
+
+proptest! {
+    // The search brackets the knee within the resolution, in a logarithmic number of steps
+    #[test]
+    fn bisection_converges_to_the_knee(knee in 1usize..20_000, start in 1usize..1000, max_tps in 1000usize..20_000, resolution in 1usize..200)
+    {
+        let mut search = BisectionSearch::new(start, max_tps, resolution);
+        let steps = run(&mut search, |tps| step(tps, tps <= knee, None));
+        if knee >= max_tps
+        {
+            prop_assert_eq!(search.passed, Some(max_tps));
+            prop_assert_eq!(search.failed, None);
+        }
+        else
+        {
+            let failed = search.failed.unwrap();
+            prop_assert!(failed > knee);
+            prop_assert!(search.passed.map_or(true, |passed| passed <= knee));
+            // closed within the resolution, or no integer TPS is left between the bounds
+            let low = search.passed.unwrap_or(0);
+            prop_assert!(failed - low <= resolution || failed - low == 1, "bracket [{}, {}]", low, failed);
+        }
+        let bound = 2 * (usize::BITS - max_tps.leading_zeros()) as usize + 2;
+        prop_assert!(steps.len() <= bound, "{} steps", steps.len());
+        prop_assert_eq!(search.stop_reason(), None);
+    }
+}
+
+#[test]
+fn bisection_stops_at_max_tps_when_everything_passes()
+{
+    let mut search = BisectionSearch::new(100, 1000, 50);
+    let steps = run(&mut search, |tps| step(tps, true, None));
+    let tried: Vec<usize> = steps.iter().map(|step| step.tps).collect();
+    assert_eq!(tried, vec![100, 200, 400, 800, 1000]);
+}
+
+#[test]
+fn bisection_below_the_start_never_tries_zero()
+{
+    let mut search = BisectionSearch::new(100, 1000, 1);
+    run(&mut search, |tps| step(tps, false, None));
+    assert_eq!(search.passed, None);
+    assert_eq!(search.failed, Some(1));
+}
+
+#[test]
+fn validator_crash_ends_the_bisection()
+{
+    let mut search = BisectionSearch::new(100, 10_000, 10);
+    let crash = fired(StopRule::ValidatorCrash);
+    let steps = run(&mut search, |tps| {
+        if tps >= 400 { step(tps, true, Some(crash.clone())) } else { step(tps, true, None) }
+    });
+    assert_eq!(steps.len(), 3);
+    assert_eq!(search.passed, Some(200));
+    assert_eq!(search.failed, None);
+    assert_eq!(search.stop_reason(), Some(crash.message.as_str()));
+    let knee = KneeReport::new(0, 1, &Slo::default(), &Search::Bisection(search), steps);
+    assert_eq!(knee.knee_tps, Some(200));
+    assert_eq!(knee.stop_reason.as_deref(), Some(crash.message.as_str()));
+    assert_eq!(knee.stopped_by.map(|fired| fired.rule), Some(StopRule::ValidatorCrash));
+}
+
+// The rule that failed the lowest failing step is the one reported, not the rule of the first failing step
+#[test]
+fn bisection_reports_the_rule_at_the_knee()
+{
+    let mut search = BisectionSearch::new(1600, 10_000, 10);
+    let steps = run(&mut search, |tps| match tps {
+        0..=500 => step(tps, true, None),
+        501..=1000 => step(tps, true, Some(fired(StopRule::Throughput { min: 0.8 }))),
+        _ => step(tps, false, Some(fired(StopRule::MeanLatency { max: 10.0 }))),
+    });
+    assert_eq!(search.failed, Some(506));
+    let knee = KneeReport::new(0, 1, &Slo::default(), &Search::Bisection(search), steps);
+    assert_eq!(knee.knee_tps, Some(500));
+    assert_eq!(knee.first_violation_tps, Some(506));
+    assert_eq!(knee.stopped_by.map(|fired| fired.rule), Some(StopRule::Throughput { min: 0.8 }));
+    assert!(knee.stop_reason.is_some());
+}
+
+// A step failing only on the SLO leaves no rule to report
+#[test]
+fn bisection_on_the_slo_alone_reports_no_rule()
+{
+    let mut search = BisectionSearch::new(100, 10_000, 10);
+    let steps = run(&mut search, |tps| step(tps, tps <= 700, None));
+    let knee = KneeReport::new(0, 1, &Slo::default(), &Search::Bisection(search), steps);
+    assert!(knee.stopped_by.is_none());
+    assert!(knee.stop_reason.is_none());
+}
@@ -0,0 +1,165 @@
+// The deterministic parts of the workload: how the transactions are split over the shards and how a
+// trace is rescaled to the target TPS.
+use proptest::prelude::*;
+use samm_bench::workload::*;
+
+fn popularity() -> impl Strategy<Value = ShardPopularity>
+{
+    prop_oneof![
+        Just(ShardPopularity::Uniform),
+        (0.0f64..3.0).prop_map(|s| ShardPopularity::Zipf { s }),
+        (0.0f64..=1.0).prop_map(|share| ShardPopularity::HotPool { share }),
+        prop::collection::vec(0.0f64..10.0, 16).prop_map(|weights| ShardPopularity::Weights { weights }),
+    ]
+}
+
+// Sorted timestamps with ties, and amounts the plan does not clamp
+fn trace() -> impl Strategy<Value = Trace>
+{
+    prop::collection::vec((0u32..1000, any::<bool>(), 1u64..MAX_SWAP_AMOUNT), 1..50).prop_map(|rows| {
+        let mut records: Vec<TraceRecord> = rows.into_iter()
+            .map(|(timestamp, xbtc_to_usdt, amount)| TraceRecord {
+                timestamp: timestamp as f64 / 10.0,
+                direction: if xbtc_to_usdt { SwapDirection::XbtcToUsdt } else { SwapDirection::UsdtToXbtc },
+                amount: amount as f64,
+                pool: None,
+            })
+            .collect();
+        records.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Trace { records }
+    })
+}
+
+proptest! {
+    // The largest remainders give out exactly the requested total, each shard within one of its quota
+    #[test]
+    fn counts_sum_to_the_total(popularity in popularity(), num_contracts in 1usize..16, total in 0usize..100_000)
+    {
+        let weights = popularity.weights(num_contracts);
+        let sum: f64 = weights.iter().sum();
+        prop_assume!(sum > 0.0);
+        let counts = popularity.counts(num_contracts, total);
+        prop_assert_eq!(counts.len(), num_contracts);
+        prop_assert_eq!(counts.iter().sum::<usize>(), total);
+        for (count, weight) in counts.iter().zip(weights)
+        {
+            let quota = weight / sum * total as f64;
+            prop_assert!((*count as f64 - quota).abs() < 1.0 + 1e-6, "count {} quota {}", count, quota);
+        }
+    }
+
+    #[test]
+    fn uniform_counts_differ_by_at_most_one(num_contracts in 1usize..64, total in 0usize..100_000)
+    {
+        let counts = ShardPopularity::Uniform.counts(num_contracts, total);
+        let base = total / num_contracts;
+        let extra = total % num_contracts;
+        // the remainders go to the first shards
+        for (shard, count) in counts.iter().enumerate()
+        {
+            prop_assert_eq!(*count, base + usize::from(shard < extra));
+        }
+    }
+
+    // A more popular shard never gets fewer transactions
+    #[test]
+    fn zipf_counts_decrease_with_the_rank(s in 0.0f64..3.0, num_contracts in 1usize..16, total in 0usize..100_000)
+    {
+        let counts = ShardPopularity::Zipf { s }.counts(num_contracts, total);
+        prop_assert!(counts.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", counts);
+    }
+
+    // The replay keeps the order of the trace, one pass taking records / target_tps seconds,
+    // and deals the swaps to the clients in turn
+    #[test]
+    fn trace_rescaling_preserves_order(trace in trace(), target_tps in 1usize..500, num_clients in 1usize..8, num_contracts in 1usize..4,
+        duration in 1.0f64..20.0)
+    {
+        let config = TraceConfig { path: "trace.csv".into(), amount_scale: 1.0 };
+        let plan = trace.plan(&config, target_tps, num_clients, num_contracts, duration);
+        let total: usize = plan.iter().map(|txs| txs.len()).sum();
+        // the sequence of the replay, taken back from the clients
+        let replay: Vec<&PlannedTx> = (0..total).map(|i| &plan[i % num_clients][i / num_clients]).collect();
+        let n = trace.records.len();
+        let period = n as f64 / target_tps as f64;
+        let mut last = 0.0;
+        for (i, tx) in replay.iter().enumerate()
+        {
+            let record = &trace.records[i % n];
+            let send_at = tx.send_at.unwrap();
+            prop_assert!(send_at >= last, "swap {} at {} before {}", i, send_at, last);
+            prop_assert!(send_at <= duration);
+            // each pass starts at a multiple of the period and ends before the next one
+            let pass = (i / n) as f64;
+            prop_assert!(send_at >= pass * period - 1e-9 && send_at < (pass + 1.0) * period);
+            prop_assert_eq!(tx.shard, i % num_contracts);
+            let swap = tx.op.swap().unwrap();
+            prop_assert_eq!(swap.direction, record.direction);
+            prop_assert_eq!(swap.amount, record.amount as u64);
+            last = send_at;
+        }
+        // the replay only stops at the end of the duration
+        let next = &trace.records[total % n];
+        let next_at = (total / n) as f64 * period + (next.timestamp - trace.records[0].timestamp) / replay_length(&trace) * period;
+        prop_assert!(next_at > duration);
+    }
+}
+
+// The length of one pass of the trace: its span plus one average gap
+fn replay_length(trace: &Trace) -> f64
+{
+    let n = trace.records.len();
+    let span = trace.records[n - 1].timestamp - trace.records[0].timestamp;
+    if n > 1 && span > 0.0 { span + span / (n - 1) as f64 } else { 1.0 }
+}
+
+#[test]
+fn zipf_counts_follow_the_weights()
+{
+    // weights 1, 1/2, 1/3, 1/4 of a sum of 25/12: quotas 480, 240, 160, 120
+    assert_eq!(ShardPopularity::Zipf { s: 1.0 }.counts(4, 1000), vec![480, 240, 160, 120]);
+    // quotas 437.96, 218.98, 145.99, 109.49, 87.59: the four largest remainders get the four missing
+    assert_eq!(ShardPopularity::Zipf { s: 1.0 }.counts(5, 1000), vec![438, 219, 146, 109, 88]);
+    assert_eq!(ShardPopularity::Zipf { s: 0.0 }.counts(3, 10), vec![4, 3, 3]);
+}
+
+// Every group uses the prefix of the weights as long as its number of shards
+#[test]
+fn weights_are_checked_for_every_group()
+{
+    let weights = ShardPopularity::Weights { weights: vec![0.0, 0.0, 1.0] };
+    assert!(weights.validate(&[3, 5]).is_err());
+    // the groups of one and two shards would get no transaction
+    assert!(weights.validate(&[1, 3]).is_err());
+    assert!(weights.validate(&[3]).is_ok());
+    assert!(ShardPopularity::Weights { weights: vec![2.0, 0.0, 1.0] }.validate(&[1, 2, 3]).is_ok());
+}
+
+#[test]
+fn trace_without_target_tps_is_empty()
+{
+    let trace = Trace { records: vec![TraceRecord { timestamp: 0.0, direction: SwapDirection::XbtcToUsdt, amount: 1.0, pool: None }] };
+    let config = TraceConfig { path: "trace.csv".into(), amount_scale: 1.0 };
+    assert!(trace.plan(&config, 0, 2, 1, 10.0).iter().all(|txs| txs.is_empty()));
+}
+
+#[test]
+fn clamped_swaps_are_counted_once()
+{
+    let record = |amount: f64| TraceRecord { timestamp: 0.0, direction: SwapDirection::UsdtToXbtc, amount, pool: None };
+    let trace = Trace { records: vec![record(0.2), record(3.0), record(MAX_SWAP_AMOUNT as f64)] };
+    let config = TraceConfig { path: "trace.csv".into(), amount_scale: 2.0 };
+    assert_eq!(trace.clamped(&config), 2);
+    // the replay sends the clamped amounts, however many passes it makes
+    let plan = trace.plan(&config, 3, 1, 1, 10.0);
+    let amounts: Vec<u64> = plan[0].iter().take(3).map(|tx| tx.op.swap().unwrap().amount).collect();
+    assert_eq!(amounts, vec![1, 6, MAX_SWAP_AMOUNT]);
+}
+
+#[test]
+fn uniform_plan_without_transactions_is_empty()
+{
+    let plan = uniform_plan(3, 2, 0, 7, &ShardPopularity::Uniform, &OperationMix::default(), &SwapMix::default());
+    assert_eq!(plan.len(), 3);
+    assert!(plan.iter().all(|txs| txs.is_empty()));
+}